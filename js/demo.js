@@ -1,9 +1,29 @@
 import { event_loop_cb } from '../../../index_bg.wasm';
+import {
+  event_loop_key_cb,
+  event_loop_gamepad_cb,
+  event_loop_mouse_cb,
+  event_loop_touch_cb,
+} from '../../../index.js';
 
-export const { event_loop_new, event_loop_raf } = (() => {
+export const { event_loop_new, event_loop_raf, event_loop_destroy } = (() => {
   const EVENT_ANIMATION_FRAME = 0;
   const EVENT_KEY_DOWN = 1;
   const EVENT_KEY_UP = 2;
+  const EVENT_GAMEPAD_CONNECTED = 3;
+  const EVENT_GAMEPAD_DISCONNECTED = 4;
+  const EVENT_GAMEPAD_BUTTON = 5;
+  const EVENT_GAMEPAD_AXIS = 6;
+  const EVENT_MOUSE_MOVE = 7;
+  const EVENT_MOUSE_DOWN = 8;
+  const EVENT_MOUSE_UP = 9;
+  const EVENT_WHEEL = 10;
+  const EVENT_TOUCH_START = 11;
+  const EVENT_TOUCH_MOVE = 12;
+  const EVENT_TOUCH_END = 13;
+  const EVENT_BLUR = 14;
+  const EVENT_FOCUS = 15;
+  const EVENT_VISIBILITY_CHANGE = 16;
   let eventLoopsDict = new Map();
   eventLoopsDict.counter = 0;
 
@@ -16,39 +36,183 @@ export const { event_loop_new, event_loop_raf } = (() => {
     }
     return event.key.charCodeAt(0);
   };
+  // converts page coordinates to SVG user units, which are game field units
+  let fieldPoint = function (event) {
+    let svg = window.path.ownerSVGElement;
+    let point = svg.createSVGPoint();
+    point.x = event.clientX;
+    point.y = event.clientY;
+    return point.matrixTransform(svg.getScreenCTM().inverse());
+  };
 
   let EventLoop = class {
-    constructor(callback) {
+    constructor(callback, keyCallback, padCallback, mouseCallback, touchCallback) {
       let self = this;
       let id = ++eventLoopsDict.counter;
       eventLoopsDict.set(id, self);
       self.id = id;
       self.dead = false;
       self.rafId = null;
+      // the element touch listeners are attached to, once subscribed
+      self.touchTarget = null;
+      // last reported button and axis values of each gamepad, by gamepad index
+      self.pads = new Map();
 
       self.rafCb = function () {
         self.rafId = null;
         if (self.dead) {
           return;
         }
+        self.pollGamepads();
         callback(id, EVENT_ANIMATION_FRAME, 0, 0, 0);
       };
 
+      // the Gamepad API has no input events, so changes are found by polling
+      self.pollGamepads = function () {
+        let pads = navigator.getGamepads ? navigator.getGamepads() : [];
+        for (let pad of pads) {
+          if (!pad) {
+            continue;
+          }
+          // pads connected before this event loop started never fire `gamepadconnected` for it
+          if (!self.pads.has(pad.index)) {
+            self.addPad(pad.index);
+          }
+          let last = self.pads.get(pad.index);
+          pad.buttons.forEach((button, i) => {
+            if (last.buttons[i] !== button.pressed) {
+              last.buttons[i] = button.pressed;
+              padCallback(id, EVENT_GAMEPAD_BUTTON, pad.index, i, button.pressed ? 1 : 0);
+            }
+          });
+          pad.axes.forEach((value, i) => {
+            if (last.axes[i] !== value) {
+              last.axes[i] = value;
+              padCallback(id, EVENT_GAMEPAD_AXIS, pad.index, i, value);
+            }
+          });
+        }
+      };
+
+      self.addPad = function (index) {
+        self.pads.set(index, { buttons: [], axes: [] });
+        padCallback(id, EVENT_GAMEPAD_CONNECTED, index, 0, 0);
+      };
+
+      self.padConnected = function (event) {
+        if (self.dead || self.pads.has(event.gamepad.index)) {
+          return;
+        }
+        self.addPad(event.gamepad.index);
+      };
+
+      self.padDisconnected = function (event) {
+        if (self.dead) {
+          return;
+        }
+        self.pads.delete(event.gamepad.index);
+        padCallback(id, EVENT_GAMEPAD_DISCONNECTED, event.gamepad.index, 0, 0);
+      };
+
       self.keyDown = function (event) {
         if (self.dead) {
           return;
         }
-        callback(id, EVENT_KEY_DOWN, event.which, charKey(event), keyEventFlags(event));
+        keyCallback(id, EVENT_KEY_DOWN, event.code, charKey(event), keyEventFlags(event));
       };
 
       self.keyUp = function (event) {
         if (self.dead) {
           return;
         }
-        callback(id, EVENT_KEY_UP, event.which, charKey(event), keyEventFlags(event));
+        keyCallback(id, EVENT_KEY_UP, event.code, charKey(event), keyEventFlags(event));
+      };
+
+      self.mouseMove = function (event) {
+        if (self.dead) {
+          return;
+        }
+        let point = fieldPoint(event);
+        mouseCallback(id, EVENT_MOUSE_MOVE, point.x, point.y, 0);
+      };
+
+      self.mouseDown = function (event) {
+        if (self.dead) {
+          return;
+        }
+        let point = fieldPoint(event);
+        mouseCallback(id, EVENT_MOUSE_DOWN, point.x, point.y, event.button);
+      };
+
+      self.mouseUp = function (event) {
+        if (self.dead) {
+          return;
+        }
+        let point = fieldPoint(event);
+        mouseCallback(id, EVENT_MOUSE_UP, point.x, point.y, event.button);
+      };
+
+      self.wheel = function (event) {
+        if (self.dead) {
+          return;
+        }
+        mouseCallback(id, EVENT_WHEEL, event.deltaX, event.deltaY, 0);
+      };
+
+      let touchHandler = function (msg) {
+        return function (event) {
+          if (self.dead) {
+            return;
+          }
+          let touches = Array.from(event.changedTouches).filter((touch) =>
+            event.currentTarget.contains(touch.target)
+          );
+          if (touches.length === 0) {
+            return;
+          }
+          // keep the page from scrolling or zooming over the field, and from emulating mouse events
+          event.preventDefault();
+          for (let touch of touches) {
+            let point = fieldPoint(touch);
+            touchCallback(id, msg, touch.identifier, point.x, point.y);
+          }
+        };
+      };
+      self.touchStart = touchHandler(EVENT_TOUCH_START);
+      self.touchMove = touchHandler(EVENT_TOUCH_MOVE);
+      self.touchEnd = touchHandler(EVENT_TOUCH_END);
+
+      self.blur = function () {
+        if (self.dead) {
+          return;
+        }
+        // buttons still held when focus returns are reported again on the next poll
+        for (let last of self.pads.values()) {
+          last.buttons = [];
+          last.axes = [];
+        }
+        callback(id, EVENT_BLUR, 0, 0, 0);
+      };
+
+      self.focus = function () {
+        if (self.dead) {
+          return;
+        }
+        callback(id, EVENT_FOCUS, 0, 0, 0);
+      };
+
+      self.visibilityChange = function () {
+        if (self.dead) {
+          return;
+        }
+        callback(id, EVENT_VISIBILITY_CHANGE, document.hidden ? 0 : 1, 0, 0);
       };
 
       self.subscribeKeyboard();
+      self.subscribeFocus();
+      self.subscribeGamepads();
+      self.subscribeMouse();
+      self.subscribeTouch();
     }
 
     raf() {
@@ -59,6 +223,36 @@ export const { event_loop_new, event_loop_raf } = (() => {
       self.rafId = requestAnimationFrame(self.rafCb);
     }
 
+    destroy() {
+      let self = this;
+      if (self.dead) {
+        return;
+      }
+      self.dead = true;
+      if (self.rafId !== null) {
+        cancelAnimationFrame(self.rafId);
+        self.rafId = null;
+      }
+      window.removeEventListener('keydown', self.keyDown);
+      window.removeEventListener('keyup', self.keyUp);
+      window.removeEventListener('mousemove', self.mouseMove);
+      window.removeEventListener('mousedown', self.mouseDown);
+      window.removeEventListener('mouseup', self.mouseUp);
+      window.removeEventListener('wheel', self.wheel);
+      if (self.touchTarget) {
+        self.touchTarget.removeEventListener('touchstart', self.touchStart);
+        self.touchTarget.removeEventListener('touchmove', self.touchMove);
+        self.touchTarget.removeEventListener('touchend', self.touchEnd);
+        self.touchTarget.removeEventListener('touchcancel', self.touchEnd);
+      }
+      window.removeEventListener('gamepadconnected', self.padConnected);
+      window.removeEventListener('gamepaddisconnected', self.padDisconnected);
+      window.removeEventListener('blur', self.blur);
+      window.removeEventListener('focus', self.focus);
+      document.removeEventListener('visibilitychange', self.visibilityChange);
+      eventLoopsDict.delete(self.id);
+    }
+
     subscribeKeyboard() {
       let self = this;
       if (self.dead) {
@@ -67,6 +261,50 @@ export const { event_loop_new, event_loop_raf } = (() => {
       window.addEventListener('keydown', self.keyDown);
       window.addEventListener('keyup', self.keyUp);
     }
+
+    subscribeFocus() {
+      let self = this;
+      if (self.dead) {
+        return;
+      }
+      window.addEventListener('blur', self.blur);
+      window.addEventListener('focus', self.focus);
+      document.addEventListener('visibilitychange', self.visibilityChange);
+    }
+
+    subscribeMouse() {
+      let self = this;
+      if (self.dead) {
+        return;
+      }
+      window.addEventListener('mousemove', self.mouseMove);
+      window.addEventListener('mousedown', self.mouseDown);
+      window.addEventListener('mouseup', self.mouseUp);
+      window.addEventListener('wheel', self.wheel);
+    }
+
+    subscribeTouch() {
+      let self = this;
+      if (self.dead) {
+        return;
+      }
+      // only touches on the field are taken over, the rest of the page scrolls as usual
+      self.touchTarget = window.path.ownerSVGElement;
+      let options = { passive: false };
+      self.touchTarget.addEventListener('touchstart', self.touchStart, options);
+      self.touchTarget.addEventListener('touchmove', self.touchMove, options);
+      self.touchTarget.addEventListener('touchend', self.touchEnd, options);
+      self.touchTarget.addEventListener('touchcancel', self.touchEnd, options);
+    }
+
+    subscribeGamepads() {
+      let self = this;
+      if (self.dead) {
+        return;
+      }
+      window.addEventListener('gamepadconnected', self.padConnected);
+      window.addEventListener('gamepaddisconnected', self.padDisconnected);
+    }
   };
 
   let raf = function (id) {
@@ -77,10 +315,34 @@ export const { event_loop_new, event_loop_raf } = (() => {
     return true;
   };
 
+  let destroy = function (id) {
+    if (!eventLoopsDict.has(id)) {
+      return false;
+    }
+    eventLoopsDict.get(id).destroy();
+    return true;
+  };
+
   return {
-    event_loop_new: () => new EventLoop(event_loop_cb).id,
+    event_loop_new: () =>
+      new EventLoop(
+        event_loop_cb,
+        event_loop_key_cb,
+        event_loop_gamepad_cb,
+        event_loop_mouse_cb,
+        event_loop_touch_cb
+      ).id,
     event_loop_raf: raf,
+    event_loop_destroy: destroy,
   };
 })();
 
 export const svg_set_path = (str) => window.path.setAttributeNS(null, 'd', str);
+
+export const svg_set_view_box = (width, height) =>
+  window.path.ownerSVGElement.setAttributeNS(null, 'viewBox', `0 0 ${width} ${height}`);
+
+export const config_text = () => {
+  let config = document.getElementById('config');
+  return config ? config.textContent : undefined;
+};
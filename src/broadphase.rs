@@ -0,0 +1,145 @@
+use crate::math::Vec2D;
+
+/// Cells along either axis are capped at this many,
+/// so huge fields get bigger cells instead of millions of buckets.
+const MAX_CELLS_PER_AXIS: usize = 64;
+
+/// How the field is divided into cells.
+#[derive(Clone, Copy)]
+struct Layout {
+    field_size: Vec2D,
+    cols: usize,
+    rows: usize,
+}
+
+impl Layout {
+    /// Cell indices along one axis covered by `lo..=hi`, wrapped around the field.
+    fn span(lo: f64, hi: f64, cell: f64, count: usize) -> impl Iterator<Item = usize> {
+        let count = count as i64;
+        let first = (lo / cell).floor() as i64;
+        let last = (hi / cell).floor() as i64;
+        let (first, last) = if last - first + 1 >= count {
+            (0, count - 1)
+        } else {
+            (first, last)
+        };
+        (first..=last).map(move |i| i.rem_euclid(count) as usize)
+    }
+
+    /// Indices of the cells covered by the bounding box of a circle.
+    fn cells_around(self, center: Vec2D, radius: f64) -> impl Iterator<Item = usize> {
+        let cell_w = self.field_size.x / self.cols as f64;
+        let cell_h = self.field_size.y / self.rows as f64;
+        Self::span(center.y - radius, center.y + radius, cell_h, self.rows).flat_map(move |row| {
+            Self::span(center.x - radius, center.x + radius, cell_w, self.cols)
+                .map(move |col| row * self.cols + col)
+        })
+    }
+}
+
+/// A uniform grid over the wrapping field that buckets entities by the cells
+/// their bounding circles overlap, so collision tests only run on nearby pairs.
+///
+/// Buckets keep their allocations from one tick to the next.
+pub struct SpatialGrid {
+    cell_size: f64,
+    layout: Layout,
+    cells: Vec<Vec<usize>>,
+    found: Vec<usize>,
+}
+
+impl SpatialGrid {
+    /// Cells are at least `cell_size` wide and tall, stretched so that whole cells cover the field.
+    /// On fields too big for [`MAX_CELLS_PER_AXIS`] cells of that size, the cells grow to fit.
+    pub fn new(cell_size: f64) -> SpatialGrid {
+        SpatialGrid {
+            cell_size,
+            layout: Layout {
+                field_size: Vec2D::zero(),
+                cols: 0,
+                rows: 0,
+            },
+            cells: Vec::new(),
+            found: Vec::new(),
+        }
+    }
+
+    /// Empties the grid, resizing it if the field size changed.
+    pub fn clear(&mut self, field_size: &Vec2D) {
+        let layout = &mut self.layout;
+        if layout.field_size.x != field_size.x || layout.field_size.y != field_size.y {
+            layout.field_size = *field_size;
+            layout.cols = ((field_size.x / self.cell_size) as usize).clamp(1, MAX_CELLS_PER_AXIS);
+            layout.rows = ((field_size.y / self.cell_size) as usize).clamp(1, MAX_CELLS_PER_AXIS);
+            self.cells = vec![Vec::new(); layout.cols * layout.rows];
+        }
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+    }
+
+    /// Adds entity `id` with a bounding circle of `radius` around `center`.
+    pub fn insert(&mut self, id: usize, center: Vec2D, radius: f64) {
+        for cell in self.layout.cells_around(center, radius) {
+            self.cells[cell].push(id);
+        }
+    }
+
+    /// Ids of the entities that may overlap the circle, in ascending order and without duplicates.
+    pub fn query(&mut self, center: Vec2D, radius: f64) -> &[usize] {
+        self.found.clear();
+        for cell in self.layout.cells_around(center, radius) {
+            self.found.extend_from_slice(&self.cells[cell]);
+        }
+        self.found.sort_unstable();
+        self.found.dedup();
+        &self.found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_find_entities_across_the_edges() {
+        let mut grid = SpatialGrid::new(100.0);
+        grid.clear(&Vec2D {
+            x: 1000.0,
+            y: 800.0,
+        });
+        grid.insert(0, Vec2D { x: 995.0, y: 400.0 }, 10.0);
+        grid.insert(1, Vec2D { x: 500.0, y: 400.0 }, 10.0);
+        grid.insert(2, Vec2D { x: 5.0, y: 795.0 }, 0.0);
+        assert_eq!(grid.query(Vec2D { x: 5.0, y: 400.0 }, 10.0), &[0]);
+        assert_eq!(grid.query(Vec2D { x: 990.0, y: 5.0 }, 20.0), &[2]);
+        // a query covering the whole field reports everything once
+        assert_eq!(grid.query(Vec2D { x: 500.0, y: 400.0 }, 1000.0), &[0, 1, 2]);
+
+        grid.clear(&Vec2D {
+            x: 1000.0,
+            y: 800.0,
+        });
+        assert!(grid.query(Vec2D { x: 500.0, y: 400.0 }, 1000.0).is_empty());
+    }
+
+    #[test]
+    fn huge_fields_get_bigger_cells() {
+        let mut grid = SpatialGrid::new(100.0);
+        let field_size = Vec2D { x: 1e12, y: 1e12 };
+        grid.clear(&field_size);
+        assert_eq!(grid.cells.len(), MAX_CELLS_PER_AXIS * MAX_CELLS_PER_AXIS);
+        grid.insert(0, Vec2D { x: 5.0, y: 5.0 }, 10.0);
+        grid.insert(1, Vec2D { x: 5e11, y: 5e11 }, 10.0);
+        assert_eq!(
+            grid.query(
+                Vec2D {
+                    x: 1e12 - 5.0,
+                    y: 0.0
+                },
+                20.0
+            ),
+            &[0]
+        );
+    }
+}
@@ -0,0 +1,838 @@
+use crate::game::AsteroidSize;
+use crate::input::InputIndex;
+use crate::math::Vec2D;
+use std::fmt;
+
+/// How asteroids of one size class look, score and break up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AsteroidSettings {
+    pub radius: f64,
+    pub score: u64,
+    /// How many asteroids of the next smaller size it breaks into. Small asteroids don't break up.
+    pub fragments: u64,
+    /// Asteroids of this size fly off at a random speed in this range.
+    pub min_speed: f64,
+    pub max_speed: f64,
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub acceleration: f64,
+    pub speed_limit: f64,
+    pub drag: f64,
+
+    pub explosion_life: f64,
+
+    pub angular_accel: f64,
+    pub angular_limit: f64,
+    pub angular_drag: f64,
+
+    pub bullet_interval: f64,
+    pub bullet_speed: f64,
+    pub bullet_lifetime: f64,
+
+    pub delta_t: f64,
+
+    /// Settings for large, medium and small asteroids, indexed by `AsteroidSize`.
+    pub asteroid_sizes: [AsteroidSettings; 3],
+
+    /// Seconds from a saucer leaving, or the level starting, until the next saucer appears.
+    pub ufo_interval: f64,
+    pub ufo_speed: f64,
+    /// Seconds between a saucer's shots.
+    pub ufo_fire_interval: f64,
+
+    /// An extra life is awarded every time the score goes up by this much. 0 turns bonus lives off.
+    pub bonus_life_score: u64,
+    /// Bonus lives aren't awarded beyond this many spare ships.
+    pub max_lives: u64,
+
+    /// Seconds the ship is gone during a hyperspace jump.
+    pub hyperspace_transit: f64,
+    /// Seconds from the end of a hyperspace jump until the next one is possible.
+    pub hyperspace_cooldown: f64,
+    /// Chance, from 0 to 1, that the ship blows up when it comes out of hyperspace.
+    pub hyperspace_failure: f64,
+
+    pub shield_radius: f64,
+    /// Shield energy, out of a full charge of 1, used up per second with the shield up.
+    pub shield_drain: f64,
+    /// Shield energy regained per second with the shield down.
+    pub shield_recharge: f64,
+
+    /// Chance that an asteroid shot by the player drops a power-up.
+    pub powerup_chance: f64,
+    pub powerup_speed: f64,
+    /// Seconds an uncollected power-up stays on the field.
+    pub powerup_lifetime: f64,
+    /// Seconds a timed power-up lasts once collected.
+    pub powerup_duration: f64,
+    /// Replaces `bullet_interval` while rapid fire is in force.
+    pub rapid_fire_interval: f64,
+
+    /// Distance from the field center that has to be free of asteroids and saucers to respawn.
+    pub respawn_clearance: f64,
+    /// Seconds after respawning during which the ship can't be destroyed.
+    pub respawn_invulnerability: f64,
+
+    pub field_size: Vec2D,
+    /// Physical keys, as `KeyboardEvent.code` values, and the inputs they trigger.
+    pub key_binds: Vec<(String, InputIndex)>,
+    /// Gamepad buttons, in the standard gamepad layout, and the inputs they trigger.
+    pub pad_binds: Vec<(u32, InputIndex)>,
+    /// The stick axis that turns the ship proportionally to how far it is pushed.
+    pub pad_turn_axis: u32,
+    /// Stick deflection, from 0 to 1, that is ignored around the center.
+    pub pad_dead_zone: f64,
+    /// Turn the ship towards the mouse pointer, and shoot with the mouse button.
+    pub mouse_aim: bool,
+    /// Two players take turns, switching whenever a ship is lost.
+    pub two_players: bool,
+}
+
+/// Key codes name physical keys, so WASD stays in place on AZERTY or Dvorak layouts.
+pub const DEFAULT_KEYBINDS: &[(&str, InputIndex)] = &[
+    ("KeyZ", InputIndex::Shoot),
+    ("Space", InputIndex::Shoot),
+    ("ArrowUp", InputIndex::Forward),
+    ("KeyW", InputIndex::Forward),
+    ("ArrowDown", InputIndex::Backward),
+    ("KeyS", InputIndex::Backward),
+    ("ArrowLeft", InputIndex::Left),
+    ("KeyA", InputIndex::Left),
+    ("ArrowRight", InputIndex::Right),
+    ("KeyD", InputIndex::Right),
+    ("KeyP", InputIndex::Pause),
+    ("Escape", InputIndex::Pause),
+    ("KeyH", InputIndex::Hyperspace),
+    ("ShiftLeft", InputIndex::Hyperspace),
+    ("KeyX", InputIndex::Shield),
+    ("KeyE", InputIndex::Shield),
+];
+
+/// Buttons of the standard gamepad layout, see https://w3c.github.io/gamepad/#remapping
+pub const DEFAULT_PADBINDS: &[(u32, InputIndex)] = &[
+    (0, InputIndex::Shoot),      // A / Cross
+    (7, InputIndex::Forward),    // right trigger
+    (6, InputIndex::Backward),   // left trigger
+    (12, InputIndex::Forward),   // d-pad up
+    (13, InputIndex::Backward),  // d-pad down
+    (14, InputIndex::Left),      // d-pad left
+    (15, InputIndex::Right),     // d-pad right
+    (9, InputIndex::Pause),      // start
+    (3, InputIndex::Hyperspace), // Y / Triangle
+    (1, InputIndex::Shield),     // B / Circle
+];
+
+fn default_key_binds() -> Vec<(String, InputIndex)> {
+    DEFAULT_KEYBINDS
+        .iter()
+        .map(|&(code, input)| (code.to_string(), input))
+        .collect()
+}
+
+/// Returned when binding a key that already triggers another input.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BindConflict {
+    pub code: String,
+    pub bound_to: InputIndex,
+}
+
+impl fmt::Display for BindConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is already bound to {:?}", self.code, self.bound_to)
+    }
+}
+
+impl std::error::Error for BindConflict {}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new()
+    }
+}
+
+impl Config {
+    pub fn new() -> Config {
+        Config {
+            acceleration: 250.0,
+            speed_limit: 1000.0,
+            drag: 0.000005,
+
+            explosion_life: 0.3,
+
+            angular_accel: 30.0,
+            angular_limit: 4.0,
+            angular_drag: 8.0,
+
+            bullet_interval: 0.15,
+            bullet_speed: 400.0,
+            bullet_lifetime: 1.7,
+
+            // arcade scoring: 20, 50 and 100 points
+            asteroid_sizes: [
+                AsteroidSettings {
+                    radius: 50.0,
+                    score: 20,
+                    fragments: 2,
+                    min_speed: 60.0,
+                    max_speed: 100.0,
+                },
+                AsteroidSettings {
+                    radius: 25.0,
+                    score: 50,
+                    fragments: 2,
+                    min_speed: 80.0,
+                    max_speed: 150.0,
+                },
+                AsteroidSettings {
+                    radius: 12.5,
+                    score: 100,
+                    fragments: 0,
+                    min_speed: 100.0,
+                    max_speed: 200.0,
+                },
+            ],
+
+            ufo_interval: 15.0,
+            ufo_speed: 120.0,
+            ufo_fire_interval: 1.2,
+
+            bonus_life_score: 10000,
+            max_lives: 8,
+
+            hyperspace_transit: 0.5,
+            hyperspace_cooldown: 1.0,
+            hyperspace_failure: 0.1,
+
+            shield_radius: 36.0,
+            shield_drain: 0.5,
+            shield_recharge: 0.1,
+
+            powerup_chance: 0.1,
+            powerup_speed: 40.0,
+            powerup_lifetime: 10.0,
+            powerup_duration: 10.0,
+            rapid_fire_interval: 0.06,
+
+            respawn_clearance: 100.0,
+            respawn_invulnerability: 2.0,
+
+            delta_t: 1.0 / 60.0,
+            field_size: Vec2D {
+                x: 1280.0,
+                y: 820.0,
+            },
+            key_binds: default_key_binds(),
+            pad_binds: DEFAULT_PADBINDS.to_vec(),
+            pad_turn_axis: 0,
+            pad_dead_zone: 0.15,
+            mouse_aim: false,
+            two_players: false,
+        }
+    }
+
+    pub fn lookup_input_key(&self, code: &str) -> Option<InputIndex> {
+        for (key, val) in self.key_binds.iter() {
+            if key == code {
+                return Some(*val);
+            }
+        }
+        None
+    }
+
+    pub fn lookup_pad_button(&self, button: u32) -> Option<InputIndex> {
+        self.pad_binds
+            .iter()
+            .find(|&&(key, _)| key == button)
+            .map(|&(_, val)| val)
+    }
+
+    /// Binds the key `code` to `input`, in addition to any keys already bound to it.
+    /// A key can only trigger one input, so binding a key that is in use fails.
+    pub fn bind(&mut self, code: &str, input: InputIndex) -> Result<(), BindConflict> {
+        match self.lookup_input_key(code) {
+            Some(bound_to) if bound_to == input => Ok(()),
+            Some(bound_to) => Err(BindConflict {
+                code: code.to_string(),
+                bound_to,
+            }),
+            None => {
+                self.key_binds.push((code.to_string(), input));
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes the binding for `code`, returning the input it triggered.
+    pub fn unbind(&mut self, code: &str) -> Option<InputIndex> {
+        let idx = self.key_binds.iter().position(|(key, _)| key == code)?;
+        Some(self.key_binds.remove(idx).1)
+    }
+
+    /// Lists the keys bound to `input`.
+    pub fn bindings(&self, input: InputIndex) -> impl Iterator<Item = &str> {
+        self.key_binds
+            .iter()
+            .filter(move |(_, val)| *val == input)
+            .map(|(key, _)| key.as_str())
+    }
+
+    pub fn reset_key_binds(&mut self) {
+        self.key_binds = default_key_binds();
+    }
+
+    pub fn asteroid(&self, size: AsteroidSize) -> &AsteroidSettings {
+        &self.asteroid_sizes[size as usize]
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    /// A line isn't of the form `key = value`.
+    Syntax {
+        line: usize,
+    },
+    UnknownKey {
+        line: usize,
+        key: String,
+    },
+    DuplicateKey {
+        line: usize,
+        key: String,
+    },
+    /// The value of `key` couldn't be parsed.
+    BadValue {
+        line: usize,
+        key: String,
+    },
+    /// The value of `key` parsed fine, but doesn't make sense for the game.
+    OutOfRange {
+        key: &'static str,
+        value: f64,
+        requirement: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Syntax { line } => {
+                write!(f, "line {}: expected `key = value`", line)
+            }
+            ConfigError::UnknownKey { line, key } => {
+                write!(f, "line {}: unknown setting `{}`", line, key)
+            }
+            ConfigError::DuplicateKey { line, key } => {
+                write!(f, "line {}: `{}` is set more than once", line, key)
+            }
+            ConfigError::BadValue { line, key } => {
+                write!(f, "line {}: invalid value for `{}`", line, key)
+            }
+            ConfigError::OutOfRange {
+                key,
+                value,
+                requirement,
+            } => write!(f, "`{}` must be {}, got {}", key, requirement, value),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Copy, Clone)]
+enum Bound {
+    Positive,
+    NonNegative,
+    /// At least 0, but less than 1.
+    Fraction,
+}
+
+impl Bound {
+    fn check(self, value: f64) -> bool {
+        value.is_finite()
+            && match self {
+                Bound::Positive => value > 0.0,
+                Bound::NonNegative => value >= 0.0,
+                Bound::Fraction => (0.0..1.0).contains(&value),
+            }
+    }
+
+    fn requirement(self) -> &'static str {
+        match self {
+            Bound::Positive => "a positive number",
+            Bound::NonNegative => "zero or a positive number",
+            Bound::Fraction => "at least 0 and less than 1",
+        }
+    }
+}
+
+/// Defines a function listing some settings for reading and one listing the same settings
+/// for writing, so that settings can be written out or checked without a mutable config.
+/// Each entry is the setting's name, the field it is kept in and any extra values to list with it.
+macro_rules! settings {
+    (
+        $(#[$doc:meta])*
+        fn $read:ident, $write:ident($config:ident) -> [($ty:ty $(, $extra:ty)*); $len:literal] {
+            $(let [$($size:ident),+] = asteroid_sizes;)?
+            $(($name:literal, $owner:ident.$field:ident $(, $value:expr)*),)*
+        }
+    ) => {
+        $(#[$doc])*
+        fn $read($config: &Config) -> [(&'static str, &$ty $(, $extra)*); $len] {
+            $(let [$($size),+] = &$config.asteroid_sizes;)?
+            [$(($name, &$owner.$field $(, $value)*)),*]
+        }
+
+        fn $write($config: &mut Config) -> [(&'static str, &mut $ty $(, $extra)*); $len] {
+            $(let [$($size),+] = &mut $config.asteroid_sizes;)?
+            [$(($name, &mut $owner.$field $(, $value)*)),*]
+        }
+    };
+}
+
+settings! {
+    /// Every plain number setting, in the order they are written out.
+    fn numbers, numbers_mut(config) -> [(f64, Bound); 37] {
+        let [large, medium, small] = asteroid_sizes;
+        ("acceleration", config.acceleration, Bound::NonNegative),
+        ("speed_limit", config.speed_limit, Bound::Positive),
+        ("drag", config.drag, Bound::NonNegative),
+        ("explosion_life", config.explosion_life, Bound::Positive),
+        ("angular_accel", config.angular_accel, Bound::NonNegative),
+        ("angular_limit", config.angular_limit, Bound::Positive),
+        ("angular_drag", config.angular_drag, Bound::NonNegative),
+        ("bullet_interval", config.bullet_interval, Bound::NonNegative),
+        ("bullet_speed", config.bullet_speed, Bound::Positive),
+        ("bullet_lifetime", config.bullet_lifetime, Bound::Positive),
+        ("delta_t", config.delta_t, Bound::Positive),
+        ("large_asteroid_radius", large.radius, Bound::Positive),
+        ("large_asteroid_min_speed", large.min_speed, Bound::NonNegative),
+        ("large_asteroid_max_speed", large.max_speed, Bound::NonNegative),
+        ("medium_asteroid_radius", medium.radius, Bound::Positive),
+        ("medium_asteroid_min_speed", medium.min_speed, Bound::NonNegative),
+        ("medium_asteroid_max_speed", medium.max_speed, Bound::NonNegative),
+        ("small_asteroid_radius", small.radius, Bound::Positive),
+        ("small_asteroid_min_speed", small.min_speed, Bound::NonNegative),
+        ("small_asteroid_max_speed", small.max_speed, Bound::NonNegative),
+        ("ufo_interval", config.ufo_interval, Bound::NonNegative),
+        ("ufo_speed", config.ufo_speed, Bound::Positive),
+        ("ufo_fire_interval", config.ufo_fire_interval, Bound::Positive),
+        ("pad_dead_zone", config.pad_dead_zone, Bound::Fraction),
+        ("hyperspace_transit", config.hyperspace_transit, Bound::NonNegative),
+        ("hyperspace_cooldown", config.hyperspace_cooldown, Bound::NonNegative),
+        ("hyperspace_failure", config.hyperspace_failure, Bound::Fraction),
+        ("shield_radius", config.shield_radius, Bound::Positive),
+        ("shield_drain", config.shield_drain, Bound::NonNegative),
+        ("shield_recharge", config.shield_recharge, Bound::NonNegative),
+        ("powerup_chance", config.powerup_chance, Bound::Fraction),
+        ("powerup_speed", config.powerup_speed, Bound::NonNegative),
+        ("powerup_lifetime", config.powerup_lifetime, Bound::Positive),
+        ("powerup_duration", config.powerup_duration, Bound::Positive),
+        ("rapid_fire_interval", config.rapid_fire_interval, Bound::NonNegative),
+        ("respawn_clearance", config.respawn_clearance, Bound::NonNegative),
+        ("respawn_invulnerability", config.respawn_invulnerability, Bound::NonNegative),
+    }
+}
+
+settings! {
+    /// Every whole number setting, in the order they are written out.
+    fn integers, integers_mut(config) -> [(u64); 7] {
+        let [large, medium, small] = asteroid_sizes;
+        ("bonus_life_score", config.bonus_life_score),
+        ("max_lives", config.max_lives),
+        ("large_asteroid_score", large.score),
+        ("large_asteroid_fragments", large.fragments),
+        ("medium_asteroid_score", medium.score),
+        ("medium_asteroid_fragments", medium.fragments),
+        ("small_asteroid_score", small.score),
+    }
+}
+
+settings! {
+    /// Every on/off setting, in the order they are written out.
+    fn flags, flags_mut(config) -> [(bool); 2] {
+        ("mouse_aim", config.mouse_aim),
+        ("two_players", config.two_players),
+    }
+}
+
+const FIELD_SIZE: &str = "field_size";
+/// Most asteroids one asteroid can break up into.
+const MAX_FRAGMENTS: u64 = 16;
+const PAD_TURN_AXIS: &str = "pad_turn_axis";
+/// Key binds are written as `bind.KeyQ = "Shoot"`.
+const BIND_PREFIX: &str = "bind.";
+/// Gamepad button binds are written as `pad_bind.9 = "Pause"`.
+const PAD_BIND_PREFIX: &str = "pad_bind.";
+
+fn parse_input(value: &str) -> Option<InputIndex> {
+    let name = value.strip_prefix('"')?.strip_suffix('"')?;
+    InputIndex::from_name(name)
+}
+
+fn parse_field_size(value: &str) -> Option<Vec2D> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    let mut parts = inner.split(',').map(|part| part.trim().parse::<f64>());
+    let x = parts.next()?.ok()?;
+    let y = parts.next()?.ok()?;
+    match parts.next() {
+        None => Some(Vec2D { x, y }),
+        Some(_) => None,
+    }
+}
+
+impl Config {
+    /// Parses settings from a flat TOML document of `key = value` lines, e.g.
+    ///
+    /// ```toml
+    /// # faster ship
+    /// acceleration = 400
+    /// field_size = [1280, 820]
+    /// ```
+    ///
+    /// Settings that aren't mentioned keep their default values.
+    /// Key binds are listed as `bind.KeyQ = "Shoot"` and gamepad buttons as `pad_bind.9 = "Pause"`,
+    /// and either kind replaces the default binds of that kind as a whole.
+    pub fn from_toml(text: &str) -> Result<Config, ConfigError> {
+        let mut config = Config::new();
+        let mut seen = Vec::new();
+        let mut binds_listed = false;
+        let mut pad_binds_listed = false;
+        for (idx, line) in text.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = match line.find('#') {
+                Some(comment) => &line[..comment],
+                None => line,
+            }
+            .trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(ConfigError::Syntax { line: line_no })?;
+            let (key, value) = (key.trim(), value.trim());
+            if seen.contains(&key) {
+                return Err(ConfigError::DuplicateKey {
+                    line: line_no,
+                    key: key.to_string(),
+                });
+            }
+            seen.push(key);
+
+            let bad_value = || ConfigError::BadValue {
+                line: line_no,
+                key: key.to_string(),
+            };
+            if key == FIELD_SIZE {
+                config.field_size = parse_field_size(value).ok_or_else(bad_value)?;
+                continue;
+            }
+            if let Some(code) = key
+                .strip_prefix(BIND_PREFIX)
+                .filter(|code| !code.is_empty())
+            {
+                let input = parse_input(value).ok_or_else(bad_value)?;
+                if !binds_listed {
+                    config.key_binds.clear();
+                    binds_listed = true;
+                }
+                config.key_binds.push((code.to_string(), input));
+                continue;
+            }
+            if key == PAD_TURN_AXIS {
+                config.pad_turn_axis = value.parse().map_err(|_| bad_value())?;
+                continue;
+            }
+            if let Some(button) = key.strip_prefix(PAD_BIND_PREFIX) {
+                let button = button.parse().map_err(|_| ConfigError::UnknownKey {
+                    line: line_no,
+                    key: key.to_string(),
+                })?;
+                let input = parse_input(value).ok_or_else(bad_value)?;
+                if !pad_binds_listed {
+                    config.pad_binds.clear();
+                    pad_binds_listed = true;
+                }
+                config.pad_binds.push((button, input));
+                continue;
+            }
+            if let Some((_, flag)) = flags_mut(&mut config)
+                .into_iter()
+                .find(|(name, _)| *name == key)
+            {
+                *flag = value.parse().map_err(|_| bad_value())?;
+                continue;
+            }
+            if let Some((_, integer)) = integers_mut(&mut config)
+                .into_iter()
+                .find(|(name, _)| *name == key)
+            {
+                *integer = value.parse().map_err(|_| bad_value())?;
+                continue;
+            }
+            match numbers_mut(&mut config)
+                .into_iter()
+                .find(|(name, _, _)| *name == key)
+            {
+                Some((_, field, _)) => *field = value.parse().map_err(|_| bad_value())?,
+                None => {
+                    return Err(ConfigError::UnknownKey {
+                        line: line_no,
+                        key: key.to_string(),
+                    })
+                }
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Writes all settings out in the format read by `from_toml`.
+    pub fn to_toml(&self) -> String {
+        let mut text = String::new();
+        for (name, value, _) in numbers(self) {
+            text += &format!("{} = {:?}\n", name, value);
+        }
+        for (name, value) in integers(self) {
+            text += &format!("{} = {}\n", name, value);
+        }
+        for (name, value) in flags(self) {
+            text += &format!("{} = {}\n", name, value);
+        }
+        text += &format!(
+            "{} = [{:?}, {:?}]\n",
+            FIELD_SIZE, self.field_size.x, self.field_size.y
+        );
+        text += &format!("{} = {}\n", PAD_TURN_AXIS, self.pad_turn_axis);
+        for (code, input) in self.key_binds.iter() {
+            text += &format!("{}{} = \"{}\"\n", BIND_PREFIX, code, input.name());
+        }
+        for (button, input) in self.pad_binds.iter() {
+            text += &format!("{}{} = \"{}\"\n", PAD_BIND_PREFIX, button, input.name());
+        }
+        text
+    }
+
+    /// Checks that every setting is within a range the game can run with.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let field_size = [
+            ("field_size.x", self.field_size.x),
+            ("field_size.y", self.field_size.y),
+        ];
+        let numbers = numbers(self)
+            .map(|(key, value, bound)| (key, *value, bound))
+            .into_iter()
+            .chain(field_size.map(|(key, value)| (key, value, Bound::Positive)));
+        for (key, value, bound) in numbers {
+            if !bound.check(value) {
+                return Err(ConfigError::OutOfRange {
+                    key,
+                    value,
+                    requirement: bound.requirement(),
+                });
+            }
+        }
+        let max_speeds = [
+            "large_asteroid_max_speed",
+            "medium_asteroid_max_speed",
+            "small_asteroid_max_speed",
+        ];
+        // small asteroids don't break up, so there is no setting for their fragments
+        let fragments = ["large_asteroid_fragments", "medium_asteroid_fragments"];
+        for (key, settings) in fragments.into_iter().zip(self.asteroid_sizes.iter()) {
+            if settings.fragments > MAX_FRAGMENTS {
+                return Err(ConfigError::OutOfRange {
+                    key,
+                    value: settings.fragments as f64,
+                    requirement: "at most 16",
+                });
+            }
+        }
+        for (key, settings) in max_speeds.into_iter().zip(self.asteroid_sizes.iter()) {
+            if settings.max_speed < settings.min_speed {
+                return Err(ConfigError::OutOfRange {
+                    key,
+                    value: settings.max_speed,
+                    requirement: "at least the matching min speed",
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let text = "# comment\nacceleration = 400 # trailing\nmax_lives = 3\nmouse_aim = true\nfield_size = [640, 480.5]\n";
+        let config = Config::from_toml(text).unwrap();
+        assert_eq!(config.acceleration, 400.0);
+        assert_eq!(config.max_lives, 3);
+        assert!(config.mouse_aim);
+        assert_eq!((config.field_size.x, config.field_size.y), (640.0, 480.5));
+        assert_eq!(config.drag, Config::new().drag);
+
+        let exported = config.to_toml();
+        assert_eq!(Config::from_toml(&exported).unwrap().to_toml(), exported);
+        assert_eq!(
+            Config::from_toml("").unwrap().to_toml(),
+            Config::new().to_toml()
+        );
+    }
+
+    #[test]
+    fn malformed_settings_are_reported() {
+        assert_eq!(
+            Config::from_toml("\nacceleration 400").err(),
+            Some(ConfigError::Syntax { line: 2 })
+        );
+        assert_eq!(
+            Config::from_toml("warp_speed = 9").err(),
+            Some(ConfigError::UnknownKey {
+                line: 1,
+                key: "warp_speed".to_string()
+            })
+        );
+        assert_eq!(
+            Config::from_toml("drag = 1\ndrag = 2").err(),
+            Some(ConfigError::DuplicateKey {
+                line: 2,
+                key: "drag".to_string()
+            })
+        );
+        assert_eq!(
+            Config::from_toml("drag = fast").err(),
+            Some(ConfigError::BadValue {
+                line: 1,
+                key: "drag".to_string()
+            })
+        );
+        assert_eq!(
+            Config::from_toml("field_size = [1, 2, 3]").err(),
+            Some(ConfigError::BadValue {
+                line: 1,
+                key: "field_size".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        assert!(matches!(
+            Config::from_toml("delta_t = 0"),
+            Err(ConfigError::OutOfRange { key: "delta_t", .. })
+        ));
+        assert!(matches!(
+            Config::from_toml("drag = -1"),
+            Err(ConfigError::OutOfRange { key: "drag", .. })
+        ));
+        assert!(matches!(
+            Config::from_toml("bullet_speed = inf"),
+            Err(ConfigError::OutOfRange {
+                key: "bullet_speed",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Config::from_toml("hyperspace_failure = 1"),
+            Err(ConfigError::OutOfRange {
+                key: "hyperspace_failure",
+                ..
+            })
+        ));
+        assert!(Config::new().validate().is_ok());
+    }
+
+    #[test]
+    fn keys_trigger_one_input_each() {
+        let mut config = Config::new();
+        assert_eq!(config.bind("KeyQ", InputIndex::Shoot), Ok(()));
+        assert_eq!(config.bind("KeyQ", InputIndex::Shoot), Ok(()));
+        assert_eq!(
+            config.bind("KeyQ", InputIndex::Left),
+            Err(BindConflict {
+                code: "KeyQ".to_string(),
+                bound_to: InputIndex::Shoot
+            })
+        );
+        assert!(config
+            .bindings(InputIndex::Shoot)
+            .any(|code| code == "KeyQ"));
+        assert!(!config.bindings(InputIndex::Left).any(|code| code == "KeyQ"));
+
+        assert_eq!(config.unbind("KeyQ"), Some(InputIndex::Shoot));
+        assert_eq!(config.unbind("KeyQ"), None);
+        config.unbind("KeyW");
+        config.reset_key_binds();
+        assert_eq!(config.lookup_input_key("KeyW"), Some(InputIndex::Forward));
+    }
+
+    #[test]
+    fn asteroid_speed_ranges_must_not_be_reversed() {
+        assert!(Config::from_toml("small_asteroid_max_speed = 100").is_ok());
+        assert!(matches!(
+            Config::from_toml("small_asteroid_max_speed = 10"),
+            Err(ConfigError::OutOfRange {
+                key: "small_asteroid_max_speed",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn key_binds_round_trip() {
+        let mut config = Config::new();
+        config.unbind("KeyW");
+        config.bind("KeyQ", InputIndex::Shoot).unwrap();
+        let parsed = Config::from_toml(&config.to_toml()).unwrap();
+        assert_eq!(parsed.key_binds, config.key_binds);
+    }
+
+    #[test]
+    fn listed_key_binds_replace_the_defaults() {
+        let config = Config::from_toml("bind.KeyQ = \"Shoot\"\nbind.KeyW = \"Forward\"").unwrap();
+        assert_eq!(config.lookup_input_key("KeyQ"), Some(InputIndex::Shoot));
+        assert_eq!(config.lookup_input_key("Space"), None);
+        assert_eq!(config.key_binds.len(), 2);
+        assert!(matches!(
+            Config::from_toml("bind.KeyQ = \"Jump\""),
+            Err(ConfigError::BadValue { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn pad_settings_round_trip() {
+        let mut config = Config::new();
+        config.pad_turn_axis = 2;
+        config.pad_binds = vec![(7, InputIndex::Shoot), (1, InputIndex::Shield)];
+        let parsed = Config::from_toml(&config.to_toml()).unwrap();
+        assert_eq!(parsed.pad_turn_axis, 2);
+        assert_eq!(parsed.pad_binds, config.pad_binds);
+        assert!(matches!(
+            Config::from_toml("pad_bind.start = \"Pause\""),
+            Err(ConfigError::UnknownKey { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn fragment_counts_are_bounded() {
+        assert!(Config::from_toml("medium_asteroid_fragments = 16").is_ok());
+        assert!(matches!(
+            Config::from_toml("large_asteroid_fragments = 100000000"),
+            Err(ConfigError::OutOfRange {
+                key: "large_asteroid_fragments",
+                ..
+            })
+        ));
+        // only settings that can be written are reported, and small asteroids never break up
+        let mut config = Config::new();
+        config.asteroid_sizes[2].fragments = 100_000_000;
+        assert!(config.validate().is_ok());
+    }
+}
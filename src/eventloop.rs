@@ -6,56 +6,203 @@ use wasm_bindgen::prelude::*;
 extern "C" {
     fn event_loop_new() -> u32;
     fn event_loop_raf(id: u32);
+    fn event_loop_destroy(id: u32);
 }
 
 const EVENT_ANIMATION_FRAME: u32 = 0;
 const EVENT_KEY_DOWN: u32 = 1;
 const EVENT_KEY_UP: u32 = 2;
+const EVENT_GAMEPAD_CONNECTED: u32 = 3;
+const EVENT_GAMEPAD_DISCONNECTED: u32 = 4;
+const EVENT_GAMEPAD_BUTTON: u32 = 5;
+const EVENT_GAMEPAD_AXIS: u32 = 6;
+const EVENT_MOUSE_MOVE: u32 = 7;
+const EVENT_MOUSE_DOWN: u32 = 8;
+const EVENT_MOUSE_UP: u32 = 9;
+const EVENT_WHEEL: u32 = 10;
+const EVENT_TOUCH_START: u32 = 11;
+const EVENT_TOUCH_MOVE: u32 = 12;
+const EVENT_TOUCH_END: u32 = 13;
+const EVENT_BLUR: u32 = 14;
+const EVENT_FOCUS: u32 = 15;
+const EVENT_VISIBILITY_CHANGE: u32 = 16;
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub enum Event {
+    /// The last event a callback receives, after `EventLoop::destroy` was called.
+    Destroyed,
     AnimationFrame,
+    /// `code` is the physical key as reported by `KeyboardEvent.code`, e.g. `"KeyW"`.
     KeyDown {
-        code: u32,
+        code: String,
         chr: Option<char>,
         flags: u32,
     },
     KeyUp {
-        code: u32,
+        code: String,
         chr: Option<char>,
         flags: u32,
     },
+    GamepadConnected {
+        gamepad: u32,
+    },
+    GamepadDisconnected {
+        gamepad: u32,
+    },
+    /// `button` is an index into `Gamepad.buttons`, in the standard layout if the pad supports it.
+    GamepadButton {
+        gamepad: u32,
+        button: u32,
+        pressed: bool,
+    },
+    /// `value` ranges from -1.0 to 1.0.
+    GamepadAxis {
+        gamepad: u32,
+        axis: u32,
+        value: f64,
+    },
+    /// Mouse coordinates are in game field units, already corrected for how the SVG is scaled.
+    MouseMove {
+        x: f64,
+        y: f64,
+    },
+    /// `button` is `MouseEvent.button`: 0 for the primary button, 2 for the secondary one.
+    MouseDown {
+        x: f64,
+        y: f64,
+        button: u32,
+    },
+    MouseUp {
+        x: f64,
+        y: f64,
+        button: u32,
+    },
+    /// Scroll amounts as reported by `WheelEvent.deltaX` and `deltaY`.
+    Wheel {
+        dx: f64,
+        dy: f64,
+    },
+    /// Each finger has its own `touch` identifier, which stays the same until it is lifted.
+    /// Coordinates are in game field units, like for mouse events.
+    TouchStart {
+        touch: u32,
+        x: f64,
+        y: f64,
+    },
+    TouchMove {
+        touch: u32,
+        x: f64,
+        y: f64,
+    },
+    /// Sent both when a finger is lifted and when the touch is cancelled.
+    TouchEnd {
+        touch: u32,
+    },
+    /// The window lost focus. Keys released from now on won't be reported,
+    /// so anything still held should be treated as released.
+    Blur,
+    Focus,
+    /// The page was hidden or shown again, e.g. by switching tabs.
+    /// No animation frames arrive while it's hidden.
+    VisibilityChange {
+        visible: bool,
+    },
 }
 
-#[no_mangle]
-pub extern "C" fn event_loop_cb(id: u32, msg: u32, p0: u32, p1: u32, p2: u32) {
-    EVENTLOOPS.with(|el| {
-        let mut el = el.borrow_mut();
-        let mut fake_event_loop = EventLoop { id };
-
-        let event = match msg {
-            EVENT_ANIMATION_FRAME => Event::AnimationFrame,
-            EVENT_KEY_DOWN => Event::KeyDown {
-                code: p0,
-                chr: p1.try_into().ok(),
-                flags: p2,
-            },
-            EVENT_KEY_UP => Event::KeyUp {
-                code: p0,
-                chr: p1.try_into().ok(),
-                flags: p2,
-            },
-            _ => return,
-        };
-
-        if let Some(cb) = el.get_mut(&id) {
-            cb(event, &mut fake_event_loop);
+fn dispatch(id: u32, event: Event) {
+    // the callback is taken out while it runs, so that it can create or destroy event loops
+    let cb = EVENTLOOPS.with(|el| el.borrow_mut().get_mut(&id).and_then(Option::take));
+    let mut cb = match cb {
+        Some(cb) => cb,
+        None => return,
+    };
+    let mut fake_event_loop = EventLoop { id };
+    cb(event, &mut fake_event_loop);
+
+    let destroyed = EVENTLOOPS.with(|el| match el.borrow_mut().get_mut(&id) {
+        Some(slot) => {
+            *slot = Some(cb);
+            None
         }
+        None => Some(cb),
     });
+    // the event loop was destroyed from inside its own callback
+    if let Some(mut cb) = destroyed {
+        cb(Event::Destroyed, &mut fake_event_loop);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn event_loop_cb(id: u32, msg: u32, p0: u32, _p1: u32, _p2: u32) {
+    let event = match msg {
+        EVENT_ANIMATION_FRAME => Event::AnimationFrame,
+        EVENT_BLUR => Event::Blur,
+        EVENT_FOCUS => Event::Focus,
+        EVENT_VISIBILITY_CHANGE => Event::VisibilityChange { visible: p0 != 0 },
+        _ => return,
+    };
+    dispatch(id, event);
+}
+
+/// Keyboard events carry the key code as a string, so they go through `wasm_bindgen`.
+#[wasm_bindgen]
+pub fn event_loop_key_cb(id: u32, msg: u32, code: String, chr: u32, flags: u32) {
+    let chr = chr.try_into().ok();
+    let event = match msg {
+        EVENT_KEY_DOWN => Event::KeyDown { code, chr, flags },
+        EVENT_KEY_UP => Event::KeyUp { code, chr, flags },
+        _ => return,
+    };
+    dispatch(id, event);
+}
+
+/// Gamepads are polled by JS on every animation frame, which reports changes through here.
+#[wasm_bindgen]
+pub fn event_loop_gamepad_cb(id: u32, msg: u32, gamepad: u32, index: u32, value: f64) {
+    let event = match msg {
+        EVENT_GAMEPAD_CONNECTED => Event::GamepadConnected { gamepad },
+        EVENT_GAMEPAD_DISCONNECTED => Event::GamepadDisconnected { gamepad },
+        EVENT_GAMEPAD_BUTTON => Event::GamepadButton {
+            gamepad,
+            button: index,
+            pressed: value != 0.0,
+        },
+        EVENT_GAMEPAD_AXIS => Event::GamepadAxis {
+            gamepad,
+            axis: index,
+            value,
+        },
+        _ => return,
+    };
+    dispatch(id, event);
+}
+
+#[wasm_bindgen]
+pub fn event_loop_mouse_cb(id: u32, msg: u32, x: f64, y: f64, button: u32) {
+    let event = match msg {
+        EVENT_MOUSE_MOVE => Event::MouseMove { x, y },
+        EVENT_MOUSE_DOWN => Event::MouseDown { x, y, button },
+        EVENT_MOUSE_UP => Event::MouseUp { x, y, button },
+        EVENT_WHEEL => Event::Wheel { dx: x, dy: y },
+        _ => return,
+    };
+    dispatch(id, event);
+}
+
+#[wasm_bindgen]
+pub fn event_loop_touch_cb(id: u32, msg: u32, touch: u32, x: f64, y: f64) {
+    let event = match msg {
+        EVENT_TOUCH_START => Event::TouchStart { touch, x, y },
+        EVENT_TOUCH_MOVE => Event::TouchMove { touch, x, y },
+        EVENT_TOUCH_END => Event::TouchEnd { touch },
+        _ => return,
+    };
+    dispatch(id, event);
 }
 
 thread_local! {
-    static EVENTLOOPS: RefCell<HashMap<u32, EventLoopCb>> = RefCell::new(HashMap::new());
+    /// Callbacks of live event loops. A callback is `None` while it is running.
+    static EVENTLOOPS: RefCell<HashMap<u32, Option<EventLoopCb>>> = RefCell::new(HashMap::new());
 }
 
 pub type EventLoopCb = Box<dyn FnMut(Event, &mut EventLoop)>;
@@ -68,7 +215,7 @@ impl EventLoop {
     pub fn new(cb: EventLoopCb) -> EventLoop {
         let id = event_loop_new();
         EVENTLOOPS.with(|el| {
-            el.borrow_mut().insert(id, cb);
+            el.borrow_mut().insert(id, Some(cb));
         });
         EventLoop { id }
     }
@@ -76,4 +223,18 @@ impl EventLoop {
     pub fn request_animation_frame(&mut self) {
         event_loop_raf(self.id);
     }
+
+    /// Unsubscribes from all page events, cancels a pending animation frame
+    /// and delivers `Event::Destroyed` to the callback, which is then dropped.
+    ///
+    /// Event loops aren't destroyed when an `EventLoop` goes out of scope,
+    /// because the callback is handed a stand-in for its event loop on every event.
+    pub fn destroy(&mut self) {
+        event_loop_destroy(self.id);
+        let cb = EVENTLOOPS.with(|el| el.borrow_mut().remove(&self.id));
+        // if the callback is running right now, `dispatch` delivers `Destroyed` once it returns
+        if let Some(Some(mut cb)) = cb {
+            cb(Event::Destroyed, self);
+        }
+    }
 }
@@ -1,116 +1,150 @@
-use crate::geom::{test_circle_point, test_circle_triangle};
+use crate::broadphase::SpatialGrid;
+pub use crate::config::Config;
+use crate::geom::{
+    test_circle_circle_wrapped, test_circle_point_wrapped, test_circle_polygon,
+    test_circle_triangle_wrapped, test_polygon_point, test_polygon_polygon, wrapped_delta,
+    wrapped_distance,
+};
 pub use crate::input::{InputIndex, Inputs};
 use crate::math::Vec2D;
-use crate::rng::{new_rng, Rng, SmallRng};
-use crate::ship::Ship;
-
-pub struct Config {
-    pub acceleration: f64,
-    pub speed_limit: f64,
-    pub drag: f64,
-
-    pub explosion_life: f64,
-
-    pub angular_accel: f64,
-    pub angular_limit: f64,
-    pub angular_drag: f64,
-
-    pub bullet_interval: f64,
-    pub bullet_speed: f64,
-    pub bullet_lifetime: f64,
-
-    pub delta_t: f64,
-
-    pub asteroid_min_size: f64,
-
-    pub field_size: Vec2D,
-    pub key_binds: Vec<(u32, InputIndex)>,
-}
-
-const DEFAULT_KEYBINDS: &[(u32, InputIndex)] = &[
-    (90, InputIndex::Shoot),    // Z
-    (32, InputIndex::Shoot),    // Space
-    (38, InputIndex::Forward),  // Up
-    (87, InputIndex::Forward),  // W
-    (40, InputIndex::Backward), // Down
-    (83, InputIndex::Backward), // S
-    (37, InputIndex::Left),     // Left
-    (65, InputIndex::Left),     // A
-    (39, InputIndex::Right),    // Right
-    (68, InputIndex::Right),    // D
-];
-
-impl Config {
-    pub fn new() -> Config {
-        Config {
-            acceleration: 250.0,
-            speed_limit: 1000.0,
-            drag: 0.000005,
-
-            explosion_life: 0.3,
-
-            angular_accel: 30.0,
-            angular_limit: 4.0,
-            angular_drag: 8.0,
-
-            bullet_interval: 0.15,
-            bullet_speed: 400.0,
-            bullet_lifetime: 1.7,
-
-            asteroid_min_size: 20.0,
-
-            delta_t: 1.0 / 60.0,
-            field_size: Vec2D {
-                x: 1280.0,
-                y: 820.0,
-            },
-            key_binds: DEFAULT_KEYBINDS.to_vec(),
-        }
-    }
-
-    pub fn lookup_input_key(&self, code: u32) -> Option<InputIndex> {
-        for &(key, val) in self.key_binds.iter() {
-            if key == code {
-                return Some(val);
-            }
-        }
-        None
-    }
-}
+#[cfg(feature = "web")]
+use crate::rng::{new_seed, WebCrypto};
+use crate::rng::{GameRng, Rng};
+use crate::ship::{Jump, Ship};
 
 #[derive(PartialEq, Eq)]
 pub enum BulletSource {
     Player,
-    #[allow(dead_code)]
     Ufo,
 }
 
 pub struct Bullet {
     pub pos: Vec2D,
+    pub prev_pos: Vec2D,
     pub speed: Vec2D,
     pub lifetime: u64,
     pub dead: bool,
     pub source: BulletSource,
+    /// Piercing bullets fly on through whatever they hit.
+    pub piercing: bool,
 }
 
 impl Bullet {
     pub fn tick(&mut self, config: &Config) {
+        self.prev_pos = self.pos;
         self.pos += self.speed.scale(config.delta_t);
         self.pos.rem_euclid_assign(&config.field_size);
     }
 }
 
 impl Bullet {
-    pub fn new(game: &Game, source: BulletSource) -> Bullet {
-        let ship = &game.ship;
-        let config = &game.config;
-        let direction = Vec2D::one().rotate(ship.angle);
+    /// Fires a bullet from `pos` along the unit vector `direction`.
+    pub fn new(
+        pos: Vec2D,
+        direction: Vec2D,
+        source: BulletSource,
+        tick: u64,
+        config: &Config,
+    ) -> Bullet {
         Bullet {
-            pos: ship.pos + direction.scale(20.0),
+            pos,
+            prev_pos: pos,
             speed: direction.scale(config.bullet_speed),
-            lifetime: game.tick + (config.bullet_lifetime / config.delta_t) as u64,
+            lifetime: tick + (config.bullet_lifetime / config.delta_t) as u64,
             dead: false,
             source,
+            piercing: false,
+        }
+    }
+
+    /// Fires a bullet from the ship's nose, turned `angle` radians away from where it faces.
+    pub fn from_ship(game: &Game, angle: f64) -> Bullet {
+        let ship = &game.ship;
+        let direction = Vec2D::one().rotate(ship.angle + angle);
+        let mut bullet = Bullet::new(
+            ship.pos + direction.scale(20.0),
+            direction,
+            BulletSource::Player,
+            game.tick,
+            &game.config,
+        );
+        bullet.piercing = game.effect_active(PowerUpKind::Piercing);
+        bullet
+    }
+}
+
+/// Angle between the bullets of a spread shot, in radians.
+const SPREAD_SHOT_ANGLE: f64 = 0.2;
+
+/// Outlines of the rock variants picked by `Asteroid::style`, for a size of 1.
+/// Every vertex is within the unit circle, so `size` is also a bounding radius.
+pub const ASTEROID_SHAPES: &[&[Vec2D]] = &[
+    &[
+        Vec2D { x: 1.0, y: 0.0 },
+        Vec2D { x: 0.59, y: 0.47 },
+        Vec2D { x: 0.34, y: 0.94 },
+        Vec2D { x: -0.32, y: 0.89 },
+        Vec2D { x: -0.49, y: 0.35 },
+        Vec2D { x: -0.99, y: 0.05 },
+        Vec2D { x: -0.71, y: -0.55 },
+        Vec2D { x: -0.29, y: -0.95 },
+        Vec2D { x: 0.21, y: -0.67 },
+        Vec2D { x: 0.79, y: -0.53 },
+    ],
+    &[
+        Vec2D { x: 0.9, y: 0.0 },
+        Vec2D { x: 0.82, y: 0.57 },
+        Vec2D { x: 0.24, y: 0.49 },
+        Vec2D { x: -0.16, y: 0.94 },
+        Vec2D { x: -0.65, y: 0.75 },
+        Vec2D { x: -0.8, y: 0.27 },
+        Vec2D { x: -0.95, y: -0.31 },
+        Vec2D { x: -0.42, y: -0.5 },
+        Vec2D { x: -0.15, y: -0.94 },
+        Vec2D { x: 0.44, y: -0.89 },
+        Vec2D { x: 0.67, y: -0.43 },
+    ],
+    &[
+        Vec2D { x: 1.0, y: 0.0 },
+        Vec2D { x: 0.66, y: 0.61 },
+        Vec2D { x: 0.1, y: 0.49 },
+        Vec2D { x: -0.53, y: 0.84 },
+        Vec2D { x: -0.89, y: 0.32 },
+        Vec2D { x: -0.96, y: -0.27 },
+        Vec2D { x: -0.33, y: -0.62 },
+        Vec2D { x: 0.18, y: -0.93 },
+        Vec2D { x: 0.68, y: -0.59 },
+    ],
+    &[
+        Vec2D { x: 0.85, y: 0.0 },
+        Vec2D { x: 0.85, y: 0.52 },
+        Vec2D { x: 0.5, y: 0.81 },
+        Vec2D { x: -0.02, y: 0.6 },
+        Vec2D { x: -0.45, y: 0.78 },
+        Vec2D { x: -0.85, y: 0.52 },
+        Vec2D { x: -0.8, y: -0.02 },
+        Vec2D { x: -0.86, y: -0.51 },
+        Vec2D { x: -0.28, y: -0.47 },
+        Vec2D { x: 0.02, y: -0.95 },
+        Vec2D { x: 0.5, y: -0.86 },
+        Vec2D { x: 0.77, y: -0.47 },
+    ],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AsteroidSize {
+    Large = 0,
+    Medium = 1,
+    Small = 2,
+}
+
+impl AsteroidSize {
+    /// The size an asteroid of this size breaks into, if it breaks up at all.
+    pub fn smaller(self) -> Option<AsteroidSize> {
+        match self {
+            AsteroidSize::Large => Some(AsteroidSize::Medium),
+            AsteroidSize::Medium => Some(AsteroidSize::Small),
+            AsteroidSize::Small => None,
         }
     }
 }
@@ -118,43 +152,181 @@ impl Bullet {
 #[derive(Clone)]
 pub struct Asteroid {
     pub pos: Vec2D,
+    pub prev_pos: Vec2D,
     pub speed: Vec2D,
     pub angle: f64,
+    pub prev_angle: f64,
     pub angle_speed: f64,
+    /// Radius of the outline, set from the settings of `size_class`.
     pub size: f64,
+    pub size_class: AsteroidSize,
+    /// Index into `ASTEROID_SHAPES`.
     pub style: usize,
     pub dead: bool,
 }
 
 impl Asteroid {
     pub fn tick(&mut self, config: &Config) {
+        self.prev_pos = self.pos;
+        self.prev_angle = self.angle;
         self.pos += self.speed.scale(config.delta_t);
         self.pos.rem_euclid_assign(&config.field_size);
         self.angle += self.angle_speed * config.delta_t;
     }
 
-    pub fn split_off(&self, config: &Config) -> Vec<Asteroid> {
-        let mut rv = Vec::new();
-        if self.size > config.asteroid_min_size {
-            let mut copy0 = self.clone();
-            let mut copy1 = self.clone();
-            let offset = Vec2D::one().rotate(self.angle).scale(self.size / 2.0);
-            copy0.size /= 2.0;
-            copy1.size /= 2.0;
-            copy0.pos += offset;
-            copy1.pos -= offset;
-            copy0.speed += offset;
-            copy1.speed -= offset;
-            rv.push(copy0);
-            rv.push(copy1);
+    /// Creates an asteroid flying in `direction`, an angle in radians,
+    /// at a random speed for its size and with a random outline.
+    pub fn new(
+        size_class: AsteroidSize,
+        pos: Vec2D,
+        direction: f64,
+        config: &Config,
+        rng: &mut GameRng,
+    ) -> Asteroid {
+        let settings = config.asteroid(size_class);
+        let speed = if settings.max_speed > settings.min_speed {
+            rng.gen_range(settings.min_speed..settings.max_speed)
+        } else {
+            settings.min_speed
+        };
+        Asteroid {
+            pos,
+            prev_pos: pos,
+            speed: Vec2D { x: speed, y: 0.0 }.rotate(direction),
+            angle: direction,
+            prev_angle: direction,
+            angle_speed: 0.6,
+            size: settings.radius,
+            size_class,
+            style: rng.gen_range(0..ASTEROID_SHAPES.len()),
+            dead: false,
+        }
+    }
+
+    pub fn outline(&self) -> &'static [Vec2D] {
+        ASTEROID_SHAPES[self.style]
+    }
+
+    /// Converts a field position into the coordinates of `outline`,
+    /// taking the short way around the field edges.
+    fn to_local(&self, point: Vec2D, field_size: &Vec2D) -> Vec2D {
+        wrapped_delta(self.pos, point, field_size)
+            .rotate(-self.angle)
+            .scale(1.0 / self.size)
+    }
+
+    /// Breaks the asteroid up into asteroids of the next smaller size, flying apart evenly spread.
+    pub fn split_off(&self, config: &Config, rng: &mut GameRng) -> Vec<Asteroid> {
+        let Some(size_class) = self.size_class.smaller() else {
+            return Vec::new();
+        };
+        let fragments = config.asteroid(self.size_class).fragments;
+        let start = rng.gen::<f64>() * TAU;
+        (0..fragments)
+            .map(|i| {
+                let direction = start + TAU * (i as f64) / (fragments as f64);
+                let offset = Vec2D::one().rotate(direction).scale(self.size / 2.0);
+                let mut fragment =
+                    Asteroid::new(size_class, self.pos + offset, direction, config, rng);
+                fragment.prev_pos = self.prev_pos + offset;
+                fragment
+            })
+            .collect()
+    }
+
+    /// Reflects the asteroid off a round obstacle at `center` moving at `speed`,
+    /// unless it is already moving away from it.
+    pub fn bounce_off(&mut self, center: Vec2D, speed: Vec2D, field_size: &Vec2D) {
+        let normal = wrapped_delta(center, self.pos, field_size).normalize();
+        let approach = (self.speed - speed).dot(normal);
+        if approach < 0.0 {
+            self.speed -= normal.scale(2.0 * approach);
         }
-        rv
     }
 }
 
-pub struct Ufo {}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UfoKind {
+    /// Fires in random directions.
+    Large,
+    /// Aims at the ship, leading its shots.
+    Small,
+}
+
+impl UfoKind {
+    pub fn radius(self) -> f64 {
+        match self {
+            UfoKind::Large => 24.0,
+            UfoKind::Small => 12.0,
+        }
+    }
+
+    pub fn score(self) -> u64 {
+        match self {
+            UfoKind::Large => 200,
+            UfoKind::Small => 1000,
+        }
+    }
+}
+
+pub struct Ufo {
+    pub kind: UfoKind,
+    pub pos: Vec2D,
+    pub prev_pos: Vec2D,
+    pub speed: Vec2D,
+    /// How much further it flies across the field before leaving.
+    pub distance_left: f64,
+    pub next_turn_tick: u64,
+    pub next_shot_tick: u64,
+    pub dead: bool,
+}
+
 impl Ufo {
-    pub fn tick(&mut self) {}
+    pub fn tick(&mut self, config: &Config) {
+        self.prev_pos = self.pos;
+        let step = self.speed.scale(config.delta_t);
+        self.distance_left -= step.x.abs();
+        self.pos += step;
+        self.pos.rem_euclid_assign(&config.field_size);
+    }
+}
+
+/// Seconds between a saucer's changes of heading.
+const UFO_TURN_INTERVAL: f64 = 1.0;
+/// How far off a small saucer's aim may be, in radians either way.
+const UFO_AIM_SPREAD: f64 = 0.1;
+
+/// Direction to fire in to hit a target at `delta` moving with `velocity`,
+/// or straight at the target if the bullet can't catch up with it.
+fn lead_direction(delta: Vec2D, velocity: Vec2D, bullet_speed: f64) -> Vec2D {
+    let c = delta.len_squared();
+    if c == 0.0 {
+        return Vec2D::one();
+    }
+    // solve |delta + velocity * t| = bullet_speed * t for the earliest t > 0
+    let a = velocity.len_squared() - bullet_speed * bullet_speed;
+    let b = 2.0 * delta.dot(velocity);
+    let t = if a.abs() < 1e-9 {
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            -1.0
+        } else {
+            let root = discriminant.sqrt();
+            let (t0, t1) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+            if t0 > 0.0 && (t0 < t1 || t1 <= 0.0) {
+                t0
+            } else {
+                t1
+            }
+        }
+    };
+    if t > 0.0 && t.is_finite() {
+        (delta + velocity.scale(t)).normalize()
+    } else {
+        delta.normalize()
+    }
 }
 
 pub struct Explosion {
@@ -180,6 +352,97 @@ pub enum GameState {
     Running,
     Respawning,
     GameOver,
+    /// Nothing moves and no timers run until the game is resumed.
+    Paused,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// Fires three bullets fanned out instead of one.
+    SpreadShot = 0,
+    /// Fires every `rapid_fire_interval` instead of every `bullet_interval`.
+    RapidFire = 1,
+    /// Bullets fly on through asteroids and saucers.
+    Piercing = 2,
+    /// Adds a life right away, up to `max_lives`.
+    ExtraLife = 3,
+}
+
+/// Number of power-ups whose effect lasts `powerup_duration`, which come first in `PowerUpKind`.
+pub const TIMED_EFFECTS: usize = 3;
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 4] = [
+        PowerUpKind::SpreadShot,
+        PowerUpKind::RapidFire,
+        PowerUpKind::Piercing,
+        PowerUpKind::ExtraLife,
+    ];
+
+    pub fn is_timed(self) -> bool {
+        (self as usize) < TIMED_EFFECTS
+    }
+}
+
+/// Radius within which the ship picks up a power-up.
+pub const POWERUP_RADIUS: f64 = 12.0;
+
+/// A pickup left behind by a destroyed asteroid, which drifts until collected or expired.
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub pos: Vec2D,
+    pub prev_pos: Vec2D,
+    pub speed: Vec2D,
+    pub lifetime: u64,
+    pub dead: bool,
+}
+
+impl PowerUp {
+    /// Drops a power-up drifting in `direction`, an angle in radians.
+    pub fn new(
+        kind: PowerUpKind,
+        pos: Vec2D,
+        direction: f64,
+        tick: u64,
+        config: &Config,
+    ) -> PowerUp {
+        PowerUp {
+            kind,
+            pos,
+            prev_pos: pos,
+            speed: Vec2D {
+                x: config.powerup_speed,
+                y: 0.0,
+            }
+            .rotate(direction),
+            lifetime: tick + (config.powerup_lifetime / config.delta_t) as u64,
+            dead: false,
+        }
+    }
+
+    pub fn tick(&mut self, config: &Config) {
+        self.prev_pos = self.pos;
+        self.pos += self.speed.scale(config.delta_t);
+        self.pos.rem_euclid_assign(&config.field_size);
+    }
+}
+
+/// Something that happened during a tick, for the page to react to, e.g. with a sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The score crossed one or more bonus life thresholds, and lives were added for them.
+    BonusLife,
+    /// The ship picked up a power-up.
+    PowerUp(PowerUpKind),
+}
+
+/// What a player leaves behind while the other one takes their turn.
+pub struct PlayerState {
+    pub score: u64,
+    pub lives: u64,
+    pub next_bonus_life: u64,
+    pub level: usize,
+    pub asteroids: Vec<Asteroid>,
 }
 
 pub struct Game {
@@ -188,95 +451,477 @@ pub struct Game {
     pub ufo: Option<Ufo>,
     pub ufo_spawn_tick: u64,
     pub lives: u64,
+    /// Score at which the next bonus life is awarded.
+    pub next_bonus_life: u64,
+    /// When the last bonus life was awarded, so the HUD can flash.
+    pub bonus_life_tick: Option<u64>,
     pub level: usize,
     pub score: u64,
+    /// Whose turn it is, 0 for the first player and 1 for the second.
+    pub player: usize,
+    /// In a two-player game, the player waiting for their turn.
+    /// `score`, `lives`, `level` and `asteroids` above belong to the player whose turn it is.
+    pub waiting_player: Option<PlayerState>,
     pub tick: u64,
     pub next_bullet_tick: u64,
+    /// The ship can't jump through hyperspace again before this tick.
+    pub next_hyperspace_tick: u64,
+    /// Nothing can destroy the freshly respawned ship before this tick.
+    pub invulnerable_until: u64,
     pub explosions: Vec<Explosion>,
     pub bullets: Vec<Bullet>,
     pub asteroids: Vec<Asteroid>,
+    pub powerups: Vec<PowerUp>,
+    /// Ticks at which the timed power-up effects wear off, indexed by `PowerUpKind`.
+    pub effect_ends: [u64; TIMED_EFFECTS],
     pub inputs: Inputs,
+    /// Whether the pause input was down last tick, so holding it toggles pause only once.
+    pub pause_held: bool,
+    /// What happened during the last tick.
+    pub events: Vec<GameEvent>,
     pub config: Config,
-    pub rng: SmallRng,
+    pub rng: GameRng,
+    pub(crate) seed: u64,
+    /// Scratch space for finding nearby pairs during collision, not part of the game state.
+    pub(crate) broadphase: SpatialGrid,
 }
 
-fn collide_asteroid_bullet(asteroid: &Asteroid, bullet: &Bullet) -> bool {
-    test_circle_point(asteroid.pos, asteroid.size, bullet.pos)
+/// The first bonus life threshold above `score`, or `u64::MAX` if bonus lives are off.
+fn bonus_life_after(score: u64, config: &Config) -> u64 {
+    match config.bonus_life_score {
+        0 => u64::MAX,
+        step => (score / step + 1).saturating_mul(step),
+    }
+}
+
+/// How many random spots a new asteroid tries before settling for one close to the ship.
+const SPAWN_TRIES: usize = 100;
+
+/// Smallest size of a broadphase cell, about the diameter of a large asteroid.
+pub(crate) const BROADPHASE_CELL_SIZE: f64 = 100.0;
+
+// entities wrap around the field edges, so every test takes the short way around them
+
+fn collide_asteroid_bullet(asteroid: &Asteroid, bullet: &Bullet, field_size: &Vec2D) -> bool {
+    test_polygon_point(
+        asteroid.outline(),
+        asteroid.to_local(bullet.pos, field_size),
+    )
 }
 
-fn collide_asteroid_ship(asteroid: &Asteroid, ship: &Ship) -> bool {
-    let tr: Vec<Vec2D> = [
+/// The ship's hull, as the triangle collisions are tested against.
+fn ship_triangle(ship: &Ship) -> [Vec2D; 3] {
+    [
         Vec2D { x: 10.0, y: 0.0 },
         Vec2D { x: -10.0, y: -5.0 },
         Vec2D { x: -10.0, y: 5.0 },
     ]
-    .iter()
     .map(|p| ship.pos + p.scale(2.2).rotate(ship.angle))
-    .collect();
-    test_circle_triangle(asteroid.pos, asteroid.size, tr[0], tr[1], tr[2])
 }
 
-use std::f64::consts::PI;
+fn collide_asteroid_ship(asteroid: &Asteroid, ship: &Ship, field_size: &Vec2D) -> bool {
+    let triangle = ship_triangle(ship).map(|p| asteroid.to_local(p, field_size));
+    test_polygon_polygon(asteroid.outline(), &triangle)
+}
+
+fn collide_asteroid_shield(asteroid: &Asteroid, ship: &Ship, config: &Config) -> bool {
+    test_circle_polygon(
+        asteroid.to_local(ship.pos, &config.field_size),
+        config.shield_radius / asteroid.size,
+        asteroid.outline(),
+    )
+}
+
+fn collide_shield_bullet(ship: &Ship, bullet: &Bullet, config: &Config) -> bool {
+    test_circle_point_wrapped(
+        ship.pos,
+        config.shield_radius,
+        bullet.pos,
+        &config.field_size,
+    )
+}
+
+fn collide_shield_ufo(ship: &Ship, ufo: &Ufo, config: &Config) -> bool {
+    test_circle_circle_wrapped(
+        ship.pos,
+        config.shield_radius,
+        ufo.pos,
+        ufo.kind.radius(),
+        &config.field_size,
+    )
+}
+
+fn collide_ship_powerup(ship: &Ship, powerup: &PowerUp, field_size: &Vec2D) -> bool {
+    test_circle_triangle_wrapped(powerup.pos, POWERUP_RADIUS, ship_triangle(ship), field_size)
+}
+
+fn collide_ship_bullet(ship: &Ship, bullet: &Bullet, field_size: &Vec2D) -> bool {
+    // a bullet is a point, which is a circle with no radius
+    test_circle_triangle_wrapped(bullet.pos, 0.0, ship_triangle(ship), field_size)
+}
+
+fn collide_ufo_bullet(ufo: &Ufo, bullet: &Bullet, field_size: &Vec2D) -> bool {
+    test_circle_point_wrapped(ufo.pos, ufo.kind.radius(), bullet.pos, field_size)
+}
+
+fn collide_asteroid_ufo(asteroid: &Asteroid, ufo: &Ufo, field_size: &Vec2D) -> bool {
+    test_circle_polygon(
+        asteroid.to_local(ufo.pos, field_size),
+        ufo.kind.radius() / asteroid.size,
+        asteroid.outline(),
+    )
+}
+
+fn collide_ship_ufo(ship: &Ship, ufo: &Ufo, field_size: &Vec2D) -> bool {
+    test_circle_triangle_wrapped(ufo.pos, ufo.kind.radius(), ship_triangle(ship), field_size)
+}
+
+use std::f64::consts::{PI, TAU};
+
+#[cfg(feature = "web")]
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
 
 impl Game {
+    #[cfg(feature = "web")]
     pub fn new() -> Game {
+        Game::with_seed(new_seed(&mut WebCrypto).expect("could not seed rng"))
+    }
+
+    /// Creates a game whose randomness is fully determined by `seed`.
+    /// Two games with the same seed fed the same inputs play out identically.
+    pub fn with_seed(seed: u64) -> Game {
+        Game::with_config(seed, Config::new())
+    }
+
+    pub fn with_config(seed: u64, config: Config) -> Game {
         let mut game = Game {
             tick: 0,
             lives: 4,
+            next_bonus_life: bonus_life_after(0, &config),
+            bonus_life_tick: None,
             level: 2,
             score: 0,
+            player: 0,
+            waiting_player: None,
             game_state: GameState::Running,
             next_bullet_tick: 0,
+            next_hyperspace_tick: 0,
+            invulnerable_until: 0,
             ship: Ship::new(),
             ufo: None,
-            ufo_spawn_tick: std::u64::MAX,
+            ufo_spawn_tick: u64::MAX,
             explosions: Vec::new(),
             bullets: Vec::new(),
             asteroids: Vec::new(),
+            powerups: Vec::new(),
+            effect_ends: [0; TIMED_EFFECTS],
             inputs: Inputs::new(),
-            config: Config::new(),
-            rng: new_rng().expect("could not seed rng"),
+            pause_held: false,
+            events: Vec::new(),
+            config,
+            rng: GameRng::from_u64(seed),
+            seed,
+            broadphase: SpatialGrid::new(BROADPHASE_CELL_SIZE),
         };
         game.spawn_level();
+        if game.config.two_players {
+            // the first player's first ship is already on the field, while the second
+            // player's comes through respawning, which takes a life like any other
+            game.waiting_player = Some(PlayerState {
+                score: 0,
+                lives: game.lives + 1,
+                next_bonus_life: game.next_bonus_life,
+                level: game.level,
+                asteroids: Vec::new(),
+            });
+            // the second player gets a field of their own
+            game.swap_players();
+            game.spawn_level();
+            game.swap_players();
+        }
         game
     }
 
+    /// The seed this game was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn spawn_level(&mut self) {
         let field_size = self.config.field_size;
         self.ship.pos = field_size.scale(0.5);
         self.ship.speed = Vec2D::zero();
         self.ship.angle = PI * -0.5;
+        self.ship.jump = None;
+        self.ship.settle();
+        if self.ufo.is_none() {
+            self.ufo_spawn_tick =
+                self.tick + (self.config.ufo_interval / self.config.delta_t) as u64;
+        }
 
+        // on small fields, a spot 300 away from the ship may not even exist
+        let safe_distance = f64::min(300.0, field_size.x.min(field_size.y) * 0.5);
         for _ in 0..self.level {
-            let mut pos;
-            loop {
+            let mut pos = Vec2D::zero();
+            for _ in 0..SPAWN_TRIES {
                 pos = Vec2D {
                     x: field_size.x * self.rng.gen::<f64>(),
                     y: field_size.y * self.rng.gen::<f64>(),
                 };
-                if (pos - self.ship.pos).len() > 300.0 {
+                if wrapped_distance(pos, self.ship.pos, &field_size) > safe_distance {
                     break;
                 }
             }
             let angle = PI * 2.0 * self.rng.gen::<f64>();
-            self.asteroids.push(Asteroid {
-                pos,
-                speed: Vec2D { x: 100.0, y: 0.0 }.rotate(angle),
-                angle,
-                angle_speed: 0.6,
-                size: 50.0,
-                style: 5,
-                dead: false,
-            });
+            let asteroid =
+                Asteroid::new(AsteroidSize::Large, pos, angle, &self.config, &mut self.rng);
+            self.asteroids.push(asteroid);
         }
     }
 
     pub fn reset(&mut self) {
-        *self = Game::new();
+        let seed = self.rng.gen();
+        let config = std::mem::take(&mut self.config);
+        *self = Game::with_config(seed, config);
+    }
+
+    fn spawn_ufo(&mut self) {
+        let config = &self.config;
+        // small saucers get more common as the score goes up
+        let small_chance = (self.score as f64 / 40000.0).clamp(0.1, 0.7);
+        let kind = if self.rng.gen::<f64>() < small_chance {
+            UfoKind::Small
+        } else {
+            UfoKind::Large
+        };
+        let direction = if self.rng.gen::<bool>() { 1.0 } else { -1.0 };
+        // saucers enter at the left edge, which is also the right one
+        let pos = Vec2D {
+            x: 0.0,
+            y: config.field_size.y * self.rng.gen::<f64>(),
+        };
+        self.ufo = Some(Ufo {
+            kind,
+            pos,
+            prev_pos: pos,
+            speed: Vec2D {
+                x: direction * config.ufo_speed,
+                y: 0.0,
+            },
+            distance_left: config.field_size.x,
+            next_turn_tick: self.tick + (UFO_TURN_INTERVAL / config.delta_t) as u64,
+            next_shot_tick: self.tick + (config.ufo_fire_interval / config.delta_t) as u64,
+            dead: false,
+        });
+    }
+
+    /// Spawns the saucer when it's due, turns and fires it, and removes it once it has crossed the field.
+    fn update_ufo(&mut self) {
+        let tick = self.tick;
+        let config = &self.config;
+        let Some(ufo) = self.ufo.as_mut() else {
+            if tick >= self.ufo_spawn_tick {
+                self.spawn_ufo();
+            }
+            return;
+        };
+        if ufo.distance_left <= 0.0 {
+            self.ufo = None;
+            self.ufo_spawn_tick = tick + (config.ufo_interval / config.delta_t) as u64;
+            return;
+        }
+
+        if tick >= ufo.next_turn_tick {
+            // zig-zag: fly straight, or diagonally up or down
+            let heading = self.rng.gen_range(-1..=1) as f64;
+            ufo.speed.y = heading * config.ufo_speed * 0.5;
+            ufo.next_turn_tick = tick + (UFO_TURN_INTERVAL / config.delta_t) as u64;
+        }
+
+        if tick >= ufo.next_shot_tick {
+            ufo.next_shot_tick = tick + (config.ufo_fire_interval / config.delta_t) as u64;
+            let direction = match ufo.kind {
+                UfoKind::Large => Vec2D::one().rotate(self.rng.gen::<f64>() * TAU),
+                UfoKind::Small if self.ship.is_present() => {
+                    let delta = (self.ship.pos - ufo.pos).wrap_delta(&config.field_size);
+                    let spread = self.rng.gen_range(-UFO_AIM_SPREAD..UFO_AIM_SPREAD);
+                    lead_direction(delta, self.ship.speed, config.bullet_speed).rotate(spread)
+                }
+                UfoKind::Small => return,
+            };
+            let pos = ufo.pos + direction.scale(ufo.kind.radius() + 4.0);
+            let bullet = Bullet::new(pos, direction, BulletSource::Ufo, tick, config);
+            self.bullets.push(bullet);
+        }
+    }
+
+    /// Freezes the game until `resume` is called. A finished game can't be paused.
+    pub fn pause(&mut self) {
+        if matches!(self.game_state, GameState::Running | GameState::Respawning) {
+            self.game_state = GameState::Paused;
+        }
+    }
+
+    /// Presses and lets go of the pause input, so that the game pauses on its next tick
+    /// the same way it would for a player, and a recording of the inputs pauses too.
+    /// Unlike the pause input, this never resumes a paused game.
+    pub fn request_pause(&mut self) {
+        if matches!(self.game_state, GameState::Running | GameState::Respawning) {
+            self.inputs.press(InputIndex::Pause);
+            self.inputs.release(InputIndex::Pause);
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.game_state == GameState::Paused {
+            // the ship is only dead while waiting to respawn
+            self.game_state = if self.ship.dead {
+                GameState::Respawning
+            } else {
+                GameState::Running
+            };
+        }
+    }
+
+    /// Starts a hyperspace jump when asked to, and ends the one in progress when it's due.
+    fn hyperspace(&mut self) {
+        let tick = self.tick;
+        let config = &self.config;
+        let ship = &mut self.ship;
+        match ship.jump {
+            Some(jump) if tick >= jump.end_tick => {
+                ship.jump = None;
+                if self.rng.gen::<f64>() < config.hyperspace_failure {
+                    self.game_state = GameState::Respawning;
+                    self.explosions.push(Explosion::new(ship.pos, tick, config));
+                    ship.dead = true;
+                }
+            }
+            Some(_) => {}
+            None => {
+                if ship.dead
+                    || !self.inputs.been_pressed(InputIndex::Hyperspace)
+                    || tick < self.next_hyperspace_tick
+                {
+                    return;
+                }
+                let from = ship.pos;
+                ship.pos = Vec2D {
+                    x: config.field_size.x * self.rng.gen::<f64>(),
+                    y: config.field_size.y * self.rng.gen::<f64>(),
+                };
+                ship.speed = Vec2D::zero();
+                ship.angular_speed = 0.0;
+                ship.shield = false;
+                ship.settle();
+                let end_tick = tick + (config.hyperspace_transit / config.delta_t) as u64;
+                ship.jump = Some(Jump {
+                    from,
+                    start_tick: tick,
+                    end_tick,
+                });
+                self.next_hyperspace_tick =
+                    end_tick + (config.hyperspace_cooldown / config.delta_t) as u64;
+            }
+        }
+    }
+
+    /// Trades the state of the current player for that of the waiting one.
+    fn swap_players(&mut self) {
+        if let Some(waiting) = self.waiting_player.as_mut() {
+            std::mem::swap(&mut self.score, &mut waiting.score);
+            std::mem::swap(&mut self.lives, &mut waiting.lives);
+            std::mem::swap(&mut self.next_bonus_life, &mut waiting.next_bonus_life);
+            std::mem::swap(&mut self.level, &mut waiting.level);
+            std::mem::swap(&mut self.asteroids, &mut waiting.asteroids);
+            self.player = 1 - self.player;
+        }
+    }
+
+    /// Hands the game over to the waiting player after a ship was lost, if they have lives left.
+    /// Bullets, the saucer and power-ups don't carry over into the other player's field.
+    fn end_turn(&mut self) {
+        if self.waiting_player.as_ref().is_none_or(|p| p.lives == 0) {
+            return;
+        }
+        self.swap_players();
+        self.bullets.clear();
+        self.powerups.clear();
+        self.ufo = None;
+        self.ufo_spawn_tick = self.tick + (self.config.ufo_interval / self.config.delta_t) as u64;
+        self.bonus_life_tick = None;
+    }
+
+    /// Whether nothing is close enough to the field center to hit the ship right after respawning.
+    pub fn spawn_clear(&self) -> bool {
+        let field_size = &self.config.field_size;
+        let center = field_size.scale(0.5);
+        let clearance = self.config.respawn_clearance;
+        let asteroids_clear = self.asteroids.iter().all(|asteroid| {
+            wrapped_distance(asteroid.pos, center, field_size) > clearance + asteroid.size
+        });
+        let ufo_clear = self.ufo.as_ref().is_none_or(|ufo| {
+            wrapped_distance(ufo.pos, center, field_size) > clearance + ufo.kind.radius()
+        });
+        asteroids_clear && ufo_clear
+    }
+
+    /// Whether the effect of a timed power-up is in force.
+    pub fn effect_active(&self, kind: PowerUpKind) -> bool {
+        kind.is_timed() && self.tick < self.effect_ends[kind as usize]
+    }
+
+    fn apply_powerup(&mut self, kind: PowerUpKind) {
+        if kind.is_timed() {
+            self.effect_ends[kind as usize] =
+                self.tick + (self.config.powerup_duration / self.config.delta_t) as u64;
+        } else if self.lives < self.config.max_lives {
+            self.lives += 1;
+            self.bonus_life_tick = Some(self.tick);
+        }
+        self.events.push(GameEvent::PowerUp(kind));
+    }
+
+    fn award_bonus_lives(&mut self) {
+        if self.score < self.next_bonus_life {
+            return;
+        }
+        // one big score can cross several thresholds at once, and each one is worth a life
+        let crossed = match self.config.bonus_life_score {
+            0 => 0,
+            step => (self.score - self.next_bonus_life) / step + 1,
+        };
+        self.next_bonus_life = bonus_life_after(self.score, &self.config);
+        let awarded = crossed.min(self.config.max_lives.saturating_sub(self.lives));
+        if awarded > 0 {
+            self.lives += awarded;
+            self.bonus_life_tick = Some(self.tick);
+            self.events.push(GameEvent::BonusLife);
+        }
     }
 
     pub fn tick(&mut self) {
+        self.events.clear();
+        let pause_down = self.inputs.been_pressed(InputIndex::Pause);
+        if pause_down && !self.pause_held {
+            if self.game_state == GameState::Paused {
+                self.resume();
+            } else {
+                self.pause();
+            }
+        }
+        self.pause_held = pause_down;
+
         match self.game_state {
+            GameState::Paused => {
+                // the tick counter stays put, which also freezes bullet and explosion lifetimes
+                self.inputs.tick();
+                return;
+            }
             GameState::GameOver => {
                 if self.inputs.is_down(InputIndex::Shoot) {
                     self.reset();
@@ -289,14 +934,19 @@ impl Game {
                     return;
                 }
 
-                let ship = &mut self.ship;
-                if self.inputs.is_down(InputIndex::Shoot) {
+                // the ship waits for the center to clear up, rather than respawning into a rock
+                if self.inputs.is_down(InputIndex::Shoot) && self.spawn_clear() {
+                    let ship = &mut self.ship;
                     self.game_state = GameState::Running;
                     self.lives -= 1;
                     ship.speed = Vec2D::zero();
                     ship.pos = self.config.field_size.scale(0.5);
                     ship.angle = std::f64::consts::PI * -0.5;
                     ship.dead = false;
+                    ship.energy = 1.0;
+                    self.effect_ends = [0; TIMED_EFFECTS];
+                    self.invulnerable_until = self.tick
+                        + (self.config.respawn_invulnerability / self.config.delta_t) as u64;
                 }
             }
             GameState::Running => {
@@ -309,37 +959,61 @@ impl Game {
         self.tick += 1;
 
         let tick = self.tick;
+        let vulnerable = tick >= self.invulnerable_until;
+        let ship_was_alive = !self.ship.dead;
         // decay bullets
         self.bullets.retain(|b| b.lifetime > tick);
         // decay explosions
         self.explosions.retain(|e| e.lifetime > tick);
+        // decay power-ups
+        self.powerups.retain(|p| p.lifetime > tick);
 
         {
             // move entities
             let inputs = &self.inputs;
             let config = &self.config;
             let ship = &mut self.ship;
-            ship.tick(inputs, config);
+            if ship.jump.is_none() {
+                ship.tick(inputs, config);
+            } else {
+                ship.settle();
+            }
             for asteroid in self.asteroids.iter_mut() {
                 asteroid.tick(config);
             }
             for bullet in self.bullets.iter_mut() {
                 bullet.tick(config);
             }
+            for powerup in self.powerups.iter_mut() {
+                powerup.tick(config);
+            }
             if let Some(ufo) = self.ufo.as_mut() {
-                ufo.tick();
+                ufo.tick(config);
             }
         }
+        self.update_ufo();
+        self.hyperspace();
 
-        if !self.ship.dead {
+        if self.ship.is_present() {
             // shoot
             let inputs = &self.inputs;
             let config = &self.config;
             if inputs.been_pressed(InputIndex::Shoot) && self.tick >= self.next_bullet_tick {
-                self.next_bullet_tick =
-                    self.tick + (config.bullet_interval / config.delta_t) as u64;
-                let bullet = Bullet::new(self, BulletSource::Player);
-                self.bullets.push(bullet);
+                let interval = if self.effect_active(PowerUpKind::RapidFire) {
+                    config.rapid_fire_interval
+                } else {
+                    config.bullet_interval
+                };
+                self.next_bullet_tick = self.tick + (interval / config.delta_t) as u64;
+                if self.effect_active(PowerUpKind::SpreadShot) {
+                    for angle in [-SPREAD_SHOT_ANGLE, 0.0, SPREAD_SHOT_ANGLE] {
+                        let bullet = Bullet::from_ship(self, angle);
+                        self.bullets.push(bullet);
+                    }
+                } else {
+                    let bullet = Bullet::from_ship(self, 0.0);
+                    self.bullets.push(bullet);
+                }
             }
         }
 
@@ -350,24 +1024,47 @@ impl Game {
             let explosions = &mut self.explosions;
             let bullets = &mut self.bullets;
             let config = &self.config;
+            let grid = &mut self.broadphase;
+            let rng = &mut self.rng;
             let mut new_asteroids = Vec::new();
             let mut new_explosions = Vec::new();
+            let mut new_powerups = Vec::new();
             let mut score_change = 0;
+            grid.clear(&config.field_size);
+            for (idx, bullet) in bullets.iter().enumerate() {
+                grid.insert(idx, bullet.pos, 0.0);
+            }
             for asteroid in asteroids.iter_mut() {
-                for bullet in bullets
-                    .iter_mut()
-                    .filter(|bullet| bullet.source == BulletSource::Player)
-                {
+                // only bullets sharing a cell with the asteroid can hit it
+                for &idx in grid.query(asteroid.pos, asteroid.size) {
+                    let bullet = &mut bullets[idx];
                     // bullets and asteroids may collide multiple times
                     // the alternative is having order-dependent logic
-                    if collide_asteroid_bullet(asteroid, bullet) {
+                    if collide_asteroid_bullet(asteroid, bullet, &config.field_size) {
                         if !asteroid.dead {
-                            score_change += 100;
-                            new_asteroids.append(&mut asteroid.split_off(config));
+                            // saucers break up asteroids too, but only the player scores
+                            if bullet.source == BulletSource::Player {
+                                score_change += config.asteroid(asteroid.size_class).score;
+                                if rng.gen::<f64>() < config.powerup_chance {
+                                    let kind =
+                                        PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())];
+                                    let direction = rng.gen::<f64>() * TAU;
+                                    new_powerups.push(PowerUp::new(
+                                        kind,
+                                        asteroid.pos,
+                                        direction,
+                                        tick,
+                                        config,
+                                    ));
+                                }
+                            }
+                            new_asteroids.append(&mut asteroid.split_off(config, rng));
                             new_explosions.push(Explosion::new(asteroid.pos, tick, config));
                         }
                         asteroid.dead = true;
-                        bullet.dead = true;
+                        if !bullet.piercing {
+                            bullet.dead = true;
+                        }
                     }
                 }
             }
@@ -379,6 +1076,7 @@ impl Game {
 
             asteroids.append(&mut new_asteroids);
             explosions.append(&mut new_explosions);
+            self.powerups.append(&mut new_powerups);
         }
 
         {
@@ -388,12 +1086,20 @@ impl Game {
             let ship = &mut self.ship;
             let ufo = &mut self.ufo;
             let config = &self.config;
-            let collide_ship_bullet = |_: &Ship, _: &Bullet| false;
-            let collide_ufo_bullet = |_: &Ufo, _: &Bullet| false;
             for bullet in bullets.iter_mut() {
                 match bullet.source {
                     BulletSource::Ufo => {
-                        if !ship.dead && collide_ship_bullet(ship, bullet) {
+                        if !ship.is_present() {
+                            continue;
+                        }
+                        if ship.shield {
+                            // the shield soaks up the shot
+                            if collide_shield_bullet(ship, bullet, config) {
+                                bullet.dead = true;
+                            }
+                        } else if vulnerable
+                            && collide_ship_bullet(ship, bullet, &config.field_size)
+                        {
                             self.game_state = GameState::Respawning;
                             explosions.push(Explosion::new(ship.pos, tick, config));
                             ship.dead = true;
@@ -401,12 +1107,15 @@ impl Game {
                         }
                     }
                     BulletSource::Player => {
-                        if ufo
-                            .as_ref()
-                            .map_or(false, |ufo| collide_ufo_bullet(ufo, bullet))
-                        {
-                            *ufo = None;
-                            bullet.dead = true;
+                        if let Some(ufo) = ufo.as_mut() {
+                            if !ufo.dead && collide_ufo_bullet(ufo, bullet, &config.field_size) {
+                                self.score += ufo.kind.score();
+                                explosions.push(Explosion::new(ufo.pos, tick, config));
+                                ufo.dead = true;
+                                if !bullet.piercing {
+                                    bullet.dead = true;
+                                }
+                            }
                         }
                     }
                 }
@@ -421,15 +1130,21 @@ impl Game {
             let explosions = &mut self.explosions;
             let mut new_asteroids = Vec::new();
             let config = &self.config;
+            let rng = &mut self.rng;
             let ship = &mut self.ship;
             let ufo = &mut self.ufo;
 
-            let collide_asteroid_ufo = |_: &Asteroid, _: &Ufo| false;
-
             for asteroid in asteroids.iter_mut() {
                 let mut collided = false;
 
-                if !ship.dead && collide_asteroid_ship(asteroid, ship) {
+                if ship.is_present() && ship.shield {
+                    if collide_asteroid_shield(asteroid, ship, config) {
+                        asteroid.bounce_off(ship.pos, ship.speed, &config.field_size);
+                    }
+                } else if ship.is_present()
+                    && vulnerable
+                    && collide_asteroid_ship(asteroid, ship, &config.field_size)
+                {
                     self.game_state = GameState::Respawning;
                     explosions.push(Explosion::new(ship.pos, tick, config));
                     explosions.push(Explosion::new(asteroid.pos, tick, config));
@@ -437,16 +1152,16 @@ impl Game {
                     collided = true;
                 }
 
-                if ufo
-                    .as_ref()
-                    .map_or(false, |ufo| collide_asteroid_ufo(asteroid, ufo))
-                {
-                    *ufo = None;
-                    collided = true;
+                if let Some(ufo) = ufo.as_mut() {
+                    if !ufo.dead && collide_asteroid_ufo(asteroid, ufo, &config.field_size) {
+                        explosions.push(Explosion::new(ufo.pos, tick, config));
+                        ufo.dead = true;
+                        collided = true;
+                    }
                 }
 
                 if collided && !asteroid.dead {
-                    new_asteroids.append(&mut asteroid.split_off(config));
+                    new_asteroids.append(&mut asteroid.split_off(config, rng));
                     asteroid.dead = true;
                 }
             }
@@ -455,13 +1170,625 @@ impl Game {
             asteroids.append(&mut new_asteroids);
         }
 
-        {
-            let collide_ship_ufo = |_, _| false;
-            if collide_ship_ufo(&self.ship, &self.ufo) {}
+        if let Some(ufo) = self.ufo.as_mut() {
+            // ramming a saucer destroys both, and still counts as shooting it down
+            let in_reach = self.ship.is_present() && !ufo.dead;
+            if in_reach && self.ship.shield {
+                // behind the shield, only the saucer goes down
+                if collide_shield_ufo(&self.ship, ufo, &self.config) {
+                    self.score += ufo.kind.score();
+                    self.explosions
+                        .push(Explosion::new(ufo.pos, tick, &self.config));
+                    ufo.dead = true;
+                }
+            } else if in_reach
+                && vulnerable
+                && collide_ship_ufo(&self.ship, ufo, &self.config.field_size)
+            {
+                self.game_state = GameState::Respawning;
+                self.score += ufo.kind.score();
+                self.explosions
+                    .push(Explosion::new(self.ship.pos, tick, &self.config));
+                self.explosions
+                    .push(Explosion::new(ufo.pos, tick, &self.config));
+                self.ship.dead = true;
+                ufo.dead = true;
+            }
+        }
+
+        // collect power-ups
+        let mut collected = Vec::new();
+        for powerup in self.powerups.iter_mut() {
+            if self.ship.is_present()
+                && collide_ship_powerup(&self.ship, powerup, &self.config.field_size)
+            {
+                collected.push(powerup.kind);
+                powerup.dead = true;
+            }
+        }
+        self.powerups.retain(|powerup| !powerup.dead);
+        for kind in collected {
+            self.apply_powerup(kind);
         }
         // END COLLISIONS
 
+        self.award_bonus_lives();
+
+        if self.ufo.as_ref().is_some_and(|ufo| ufo.dead) {
+            self.ufo = None;
+            self.ufo_spawn_tick = tick + (self.config.ufo_interval / self.config.delta_t) as u64;
+        }
+
+        if ship_was_alive && self.ship.dead {
+            self.end_turn();
+        }
+
         // forget pressed inputs
         self.inputs.tick();
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Holds `idx` down or lets it go, through the first key bound to it.
+    pub(crate) fn hold(game: &mut Game, idx: InputIndex, down: bool) {
+        let code = game.config.bindings(idx).next().unwrap().to_string();
+        if down {
+            game.inputs.key_down(&code, &game.config);
+        } else {
+            game.inputs.key_up(&code, &game.config);
+        }
+    }
+
+    /// Sets the inputs for the coming tick, so that the ship moves, turns and shoots.
+    pub(crate) fn script(game: &mut Game) {
+        let tick = game.tick;
+        hold(game, InputIndex::Shoot, tick % 40 < 20);
+        hold(game, InputIndex::Forward, tick % 300 < 60);
+        hold(game, InputIndex::Left, tick % 200 < 70);
+    }
+
+    pub(crate) fn play(game: &mut Game, ticks: u64) {
+        for _ in 0..ticks {
+            script(game);
+            game.tick();
+        }
+    }
+
+    fn asteroid_positions(game: &Game) -> Vec<(f64, f64)> {
+        game.asteroids
+            .iter()
+            .map(|asteroid| (asteroid.pos.x, asteroid.pos.y))
+            .collect()
+    }
+
+    /// Asserts that two games have reached the same state.
+    pub(crate) fn assert_same(a: &Game, b: &Game) {
+        assert_eq!(a.tick, b.tick);
+        assert_eq!(a.score, b.score);
+        assert_eq!(a.rng, b.rng);
+        assert_eq!(
+            (a.ship.pos.x, a.ship.pos.y, a.ship.angle),
+            (b.ship.pos.x, b.ship.pos.y, b.ship.angle)
+        );
+        assert_eq!(asteroid_positions(a), asteroid_positions(b));
+    }
+
+    #[test]
+    fn same_seed_plays_out_the_same() {
+        let (mut a, mut b) = (Game::with_seed(42), Game::with_seed(42));
+        play(&mut a, 3000);
+        play(&mut b, 3000);
+        assert!(a.score > 0);
+        assert_same(&a, &b);
+
+        let mut c = Game::with_seed(43);
+        play(&mut c, 3000);
+        assert_ne!(asteroid_positions(&a), asteroid_positions(&c));
+    }
+
+    /// Leaves a single speck of an asteroid off to the side,
+    /// so the level doesn't end but nothing runs into it either.
+    fn park_asteroids(game: &mut Game) {
+        game.asteroids.truncate(1);
+        let speck = &mut game.asteroids[0];
+        speck.pos = game.config.field_size.scale(0.25);
+        speck.speed = Vec2D::zero();
+        speck.size = 1.0;
+    }
+
+    /// Puts a saucer of `kind` at `pos`, hovering in place.
+    fn place_ufo(game: &mut Game, kind: UfoKind, pos: Vec2D) {
+        game.spawn_ufo();
+        let ufo = game.ufo.as_mut().unwrap();
+        ufo.kind = kind;
+        ufo.pos = pos;
+        ufo.prev_pos = pos;
+        ufo.speed = Vec2D::zero();
+    }
+
+    #[test]
+    fn saucers_cross_the_field_and_come_back() {
+        let mut game = Game::with_seed(4);
+        park_asteroids(&mut game);
+        // keep the ship out of the saucer's way
+        game.ship.dead = true;
+        game.game_state = GameState::Respawning;
+        let interval = (game.config.ufo_interval / game.config.delta_t) as u64;
+
+        while game.ufo.is_none() {
+            game.tick();
+        }
+        assert_eq!(game.tick, interval);
+        let crossing = game.config.field_size.x / game.config.ufo_speed / game.config.delta_t;
+        while game.ufo.is_some() {
+            game.tick();
+        }
+        assert!(((game.tick - interval) as f64 - crossing).abs() <= 1.0);
+        assert_eq!(game.ufo_spawn_tick, game.tick + interval);
+    }
+
+    #[test]
+    fn shooting_a_saucer_scores_by_kind() {
+        for kind in [UfoKind::Large, UfoKind::Small] {
+            let mut game = Game::with_seed(4);
+            park_asteroids(&mut game);
+            let above = game.ship.pos - Vec2D { x: 0.0, y: 100.0 };
+            place_ufo(&mut game, kind, above);
+            hold(&mut game, InputIndex::Shoot, true);
+            for _ in 0..30 {
+                game.tick();
+            }
+            assert!(game.ufo.is_none());
+            assert_eq!(game.score, kind.score());
+        }
+    }
+
+    #[test]
+    fn saucers_lead_their_shots() {
+        let delta = Vec2D { x: 300.0, y: 0.0 };
+        let velocity = Vec2D { x: 0.0, y: 100.0 };
+        let direction = lead_direction(delta, velocity, 400.0);
+        // the bullet gets to where the target will be at the same time as the target
+        let t = delta.x / (direction.x * 400.0);
+        assert!((direction.y * 400.0 * t - velocity.y * t).abs() < 1e-9);
+
+        // a target flying away faster than the bullet is shot at directly
+        let direction = lead_direction(delta, Vec2D { x: 1000.0, y: 0.0 }, 400.0);
+        assert_eq!((direction.x, direction.y), (1.0, 0.0));
+    }
+
+    /// Fires a saucer bullet at `pos` from just above it, flying straight down.
+    fn drop_ufo_bullet(game: &mut Game, pos: Vec2D) {
+        let from = pos - Vec2D { x: 0.0, y: 20.0 };
+        let down = Vec2D { x: 0.0, y: 1.0 };
+        let bullet = Bullet::new(from, down, BulletSource::Ufo, game.tick, &game.config);
+        game.bullets.push(bullet);
+    }
+
+    #[test]
+    fn saucer_shots_destroy_the_ship() {
+        let mut game = Game::with_seed(8);
+        park_asteroids(&mut game);
+        let lives = game.lives;
+        let pos = game.ship.pos;
+        drop_ufo_bullet(&mut game, pos);
+        for _ in 0..10 {
+            game.tick();
+        }
+        assert!(game.ship.dead);
+        assert!(game.game_state == GameState::Respawning);
+        assert!(game.bullets.is_empty());
+        assert_eq!(game.lives, lives);
+    }
+
+    #[test]
+    fn ramming_a_saucer_destroys_both() {
+        let mut game = Game::with_seed(8);
+        park_asteroids(&mut game);
+        let pos = game.ship.pos;
+        place_ufo(&mut game, UfoKind::Large, pos);
+        game.tick();
+        assert!(game.ship.dead);
+        assert!(game.ufo.is_none());
+        assert_eq!(game.score, UfoKind::Large.score());
+    }
+
+    #[test]
+    fn saucers_break_asteroids_without_scoring() {
+        let mut game = Game::with_seed(8);
+        game.asteroids.truncate(1);
+        let rock = &mut game.asteroids[0];
+        rock.pos = Vec2D { x: 200.0, y: 200.0 };
+        rock.speed = Vec2D::zero();
+        let pos = rock.pos;
+        drop_ufo_bullet(&mut game, pos);
+        game.tick();
+        assert!(game.bullets.is_empty());
+        assert_eq!(game.asteroids.len(), 2);
+        assert_eq!(game.score, 0);
+
+        // flying into an asteroid is the end of a saucer, too
+        let rock = &game.asteroids[0];
+        let pos = rock.pos;
+        place_ufo(&mut game, UfoKind::Small, pos);
+        game.tick();
+        assert!(game.ufo.is_none());
+        assert_eq!(game.score, 0);
+    }
+
+    /// Replaces the field with a single resting asteroid of `size_class` at `pos`.
+    fn place_asteroid(game: &mut Game, size_class: AsteroidSize, pos: Vec2D) {
+        let mut asteroid = Asteroid::new(size_class, pos, 0.0, &game.config, &mut game.rng);
+        asteroid.speed = Vec2D::zero();
+        game.asteroids = vec![asteroid];
+    }
+
+    #[test]
+    fn asteroids_break_into_the_next_size() {
+        let config = Config::new();
+        let mut rng = GameRng::from_u64(1);
+        let pos = Vec2D { x: 100.0, y: 100.0 };
+        let sizes = [
+            (AsteroidSize::Large, Some(AsteroidSize::Medium)),
+            (AsteroidSize::Medium, Some(AsteroidSize::Small)),
+            (AsteroidSize::Small, None),
+        ];
+        for (size_class, smaller) in sizes {
+            let asteroid = Asteroid::new(size_class, pos, 0.0, &config, &mut rng);
+            assert_eq!(asteroid.size, config.asteroid(size_class).radius);
+            let fragments = asteroid.split_off(&config, &mut rng);
+            match smaller {
+                Some(smaller) => {
+                    assert_eq!(
+                        fragments.len() as u64,
+                        config.asteroid(size_class).fragments
+                    );
+                    assert!(fragments.iter().all(|f| f.size_class == smaller));
+                }
+                None => assert!(fragments.is_empty()),
+            }
+        }
+    }
+
+    #[test]
+    fn asteroids_score_by_size() {
+        for size_class in [
+            AsteroidSize::Large,
+            AsteroidSize::Medium,
+            AsteroidSize::Small,
+        ] {
+            let mut game = Game::with_seed(2);
+            let above = game.ship.pos - Vec2D { x: 0.0, y: 150.0 };
+            place_asteroid(&mut game, size_class, above);
+            hold(&mut game, InputIndex::Shoot, true);
+            for _ in 0..30 {
+                game.tick();
+            }
+            assert_eq!(game.score, game.config.asteroid(size_class).score);
+        }
+    }
+
+    /// Presses `idx` for a single tick.
+    fn tap(game: &mut Game, idx: InputIndex) {
+        hold(game, idx, true);
+        game.tick();
+        hold(game, idx, false);
+    }
+
+    /// Jumps into hyperspace and ticks until the ship comes out again.
+    fn jump(game: &mut Game) {
+        tap(game, InputIndex::Hyperspace);
+        let end_tick = game.ship.jump.unwrap().end_tick;
+        while game.tick < end_tick {
+            game.tick();
+        }
+    }
+
+    #[test]
+    fn ships_in_hyperspace_can_not_be_hit() {
+        let mut game = Game::with_seed(6);
+        park_asteroids(&mut game);
+        game.config.hyperspace_failure = 0.0;
+        let from = game.ship.pos;
+        tap(&mut game, InputIndex::Hyperspace);
+        let jump = game.ship.jump.unwrap();
+        let transit = (game.config.hyperspace_transit / game.config.delta_t) as u64;
+        assert_eq!(jump.end_tick, game.tick + transit);
+        assert_eq!((jump.from.x, jump.from.y), (from.x, from.y));
+        assert!(!game.ship.is_present());
+
+        // a shot at where the ship is headed goes right through
+        let pos = game.ship.pos;
+        drop_ufo_bullet(&mut game, pos);
+        game.tick();
+        assert!(!game.ship.dead);
+        assert!(!game.bullets.is_empty());
+
+        while game.tick < jump.end_tick {
+            game.tick();
+        }
+        assert!(game.ship.is_present());
+        assert!(game.game_state == GameState::Running);
+    }
+
+    #[test]
+    fn hyperspace_cools_down_after_a_jump() {
+        let mut game = Game::with_seed(6);
+        park_asteroids(&mut game);
+        game.config.hyperspace_failure = 0.0;
+        jump(&mut game);
+        let ready = game.next_hyperspace_tick;
+        let cooldown = (game.config.hyperspace_cooldown / game.config.delta_t) as u64;
+        assert_eq!(ready, game.tick + cooldown);
+
+        while game.tick + 2 < ready {
+            game.tick();
+        }
+        tap(&mut game, InputIndex::Hyperspace);
+        assert!(game.ship.jump.is_none());
+        tap(&mut game, InputIndex::Hyperspace);
+        assert!(game.ship.jump.is_some());
+    }
+
+    #[test]
+    fn failed_jumps_lose_the_ship() {
+        let mut game = Game::with_seed(6);
+        park_asteroids(&mut game);
+        game.config.hyperspace_failure = 1.0;
+        let lives = game.lives;
+        jump(&mut game);
+        assert!(game.ship.dead);
+        assert!(game.game_state == GameState::Respawning);
+
+        // from there on it's like any other lost ship
+        tap(&mut game, InputIndex::Shoot);
+        assert!(game.game_state == GameState::Running);
+        assert!(!game.ship.dead);
+        assert_eq!(game.lives, lives - 1);
+    }
+
+    #[test]
+    fn shields_bounce_asteroids_and_stop_shots() {
+        let mut game = Game::with_seed(10);
+        let right = game.ship.pos + Vec2D { x: 80.0, y: 0.0 };
+        place_asteroid(&mut game, AsteroidSize::Medium, right);
+        game.asteroids[0].speed = Vec2D { x: -100.0, y: 0.0 };
+        hold(&mut game, InputIndex::Shield, true);
+        for _ in 0..30 {
+            game.tick();
+        }
+        assert!(game.ship.shield);
+        assert!(!game.ship.dead);
+        assert_eq!(game.asteroids.len(), 1);
+        assert!(game.asteroids[0].speed.x > 0.0);
+
+        let pos = game.ship.pos;
+        drop_ufo_bullet(&mut game, pos);
+        game.tick();
+        assert!(!game.ship.dead);
+        assert!(game.bullets.is_empty());
+    }
+
+    #[test]
+    fn shields_drain_and_recharge() {
+        let mut game = Game::with_seed(10);
+        park_asteroids(&mut game);
+        let config = game.config.clone();
+        hold(&mut game, InputIndex::Shield, true);
+        game.tick();
+        assert!(game.ship.shield);
+        assert_eq!(game.ship.energy, 1.0 - config.shield_drain * config.delta_t);
+
+        // holding on after the charge runs out keeps the shield down
+        let drain_ticks = (1.0 / (config.shield_drain * config.delta_t)).ceil() as u64;
+        for _ in 0..=drain_ticks {
+            game.tick();
+        }
+        assert_eq!(game.ship.energy, 0.0);
+        assert!(!game.ship.shield);
+
+        hold(&mut game, InputIndex::Shield, false);
+        game.tick();
+        assert!(!game.ship.shield);
+        assert_eq!(game.ship.energy, config.shield_recharge * config.delta_t);
+    }
+
+    /// Drops a power-up of `kind` right on the ship and ticks once, so it gets picked up.
+    fn collect(game: &mut Game, kind: PowerUpKind) {
+        let powerup = PowerUp::new(kind, game.ship.pos, 0.0, game.tick, &game.config);
+        game.powerups.push(powerup);
+        game.tick();
+    }
+
+    #[test]
+    fn shot_asteroids_drop_power_ups() {
+        for (chance, drops) in [(1.0, 1), (0.0, 0)] {
+            let mut game = Game::with_seed(12);
+            game.config.powerup_chance = chance;
+            let above = game.ship.pos - Vec2D { x: 0.0, y: 150.0 };
+            place_asteroid(&mut game, AsteroidSize::Small, above);
+            tap(&mut game, InputIndex::Shoot);
+            while !game.bullets.is_empty() {
+                game.tick();
+            }
+            assert!(game.score > 0);
+            assert_eq!(game.powerups.len(), drops);
+        }
+    }
+
+    #[test]
+    fn timed_power_ups_wear_off() {
+        let mut game = Game::with_seed(12);
+        park_asteroids(&mut game);
+        collect(&mut game, PowerUpKind::RapidFire);
+        assert!(game.powerups.is_empty());
+        assert_eq!(game.events, [GameEvent::PowerUp(PowerUpKind::RapidFire)]);
+        assert!(game.effect_active(PowerUpKind::RapidFire));
+        assert!(!game.effect_active(PowerUpKind::SpreadShot));
+
+        let duration = (game.config.powerup_duration / game.config.delta_t) as u64;
+        for _ in 0..duration {
+            game.tick();
+        }
+        assert!(!game.effect_active(PowerUpKind::RapidFire));
+    }
+
+    /// Ticks until the gun is ready to fire again.
+    fn reload(game: &mut Game) {
+        while game.tick + 1 < game.next_bullet_tick {
+            game.tick();
+        }
+    }
+
+    #[test]
+    fn power_ups_change_the_guns() {
+        let mut game = Game::with_seed(12);
+        park_asteroids(&mut game);
+        collect(&mut game, PowerUpKind::SpreadShot);
+        tap(&mut game, InputIndex::Shoot);
+        assert_eq!(game.bullets.len(), 3);
+
+        collect(&mut game, PowerUpKind::RapidFire);
+        reload(&mut game);
+        tap(&mut game, InputIndex::Shoot);
+        let interval = (game.config.rapid_fire_interval / game.config.delta_t) as u64;
+        assert_eq!(game.next_bullet_tick, game.tick + interval);
+
+        // piercing shots fly on through what they hit
+        collect(&mut game, PowerUpKind::Piercing);
+        let above = game.ship.pos - Vec2D { x: 0.0, y: 150.0 };
+        place_asteroid(&mut game, AsteroidSize::Small, above);
+        game.bullets.clear();
+        reload(&mut game);
+        tap(&mut game, InputIndex::Shoot);
+        while game.score == 0 && game.bullets.len() == 3 {
+            game.tick();
+        }
+        assert!(game.asteroids.is_empty());
+        assert_eq!(game.bullets.len(), 3);
+    }
+
+    #[test]
+    fn extra_lives_stop_at_the_maximum() {
+        let mut game = Game::with_seed(12);
+        park_asteroids(&mut game);
+        let lives = game.lives;
+        collect(&mut game, PowerUpKind::ExtraLife);
+        assert_eq!(game.lives, lives + 1);
+        assert!(!game.effect_active(PowerUpKind::ExtraLife));
+
+        game.lives = game.config.max_lives;
+        collect(&mut game, PowerUpKind::ExtraLife);
+        assert_eq!(game.lives, game.config.max_lives);
+    }
+
+    #[test]
+    fn ships_wait_for_a_clear_center_to_respawn() {
+        let mut game = Game::with_seed(14);
+        park_asteroids(&mut game);
+        let center = game.ship.pos;
+        game.ship.dead = true;
+        game.game_state = GameState::Respawning;
+        place_ufo(&mut game, UfoKind::Large, center);
+        assert!(!game.spawn_clear());
+        hold(&mut game, InputIndex::Shoot, true);
+        game.tick();
+        assert!(game.game_state == GameState::Respawning);
+
+        game.ufo = None;
+        game.tick();
+        assert!(game.game_state == GameState::Running);
+        assert!(!game.ship.dead);
+    }
+
+    #[test]
+    fn respawned_ships_are_briefly_invulnerable() {
+        let mut game = Game::with_seed(14);
+        park_asteroids(&mut game);
+        game.ship.dead = true;
+        game.game_state = GameState::Respawning;
+        tap(&mut game, InputIndex::Shoot);
+        let window = (game.config.respawn_invulnerability / game.config.delta_t) as u64;
+        assert_eq!(game.invulnerable_until, game.tick - 1 + window);
+
+        let pos = game.ship.pos;
+        drop_ufo_bullet(&mut game, pos);
+        game.tick();
+        assert!(!game.ship.dead);
+
+        game.bullets.clear();
+        while game.tick + 1 < game.invulnerable_until {
+            game.tick();
+        }
+        let pos = game.ship.pos;
+        drop_ufo_bullet(&mut game, pos);
+        game.tick();
+        assert!(game.ship.dead);
+    }
+
+    #[test]
+    fn two_players_fly_the_same_number_of_ships() {
+        let config = Config {
+            two_players: true,
+            ..Config::new()
+        };
+        let mut game = Game::with_config(9, config);
+        game.inputs.press(InputIndex::Shoot);
+        game.inputs.press(InputIndex::Left);
+        let mut ships = [1, 0];
+        for _ in 0..1_000_000 {
+            let was_dead = game.ship.dead;
+            game.tick();
+            if was_dead && !game.ship.dead {
+                ships[game.player] += 1;
+            }
+            if game.game_state == GameState::GameOver {
+                break;
+            }
+        }
+        assert!(game.game_state == GameState::GameOver);
+        assert_eq!(ships, [5, 5]);
+    }
+
+    #[test]
+    fn small_fields_spawn_levels() {
+        for field_size in [[400.0, 400.0], [10.0, 10.0], [2000.0, 5.0]] {
+            let config = Config::from_toml(&format!(
+                "field_size = [{}, {}]",
+                field_size[0], field_size[1]
+            ))
+            .unwrap();
+            let game = Game::with_config(1, config);
+            assert_eq!(game.asteroids.len(), game.level);
+        }
+    }
+
+    #[test]
+    fn asteroid_shapes_fit_their_size() {
+        for shape in ASTEROID_SHAPES {
+            assert!(shape.iter().all(|vertex| vertex.len() <= 1.0));
+        }
+    }
+
+    #[test]
+    fn every_bonus_life_threshold_crossed_counts() {
+        let mut game = Game::with_seed(4);
+        game.config.bonus_life_score = 1000;
+        game.next_bonus_life = 1000;
+        let lives = game.lives;
+        game.score = 3500;
+        game.award_bonus_lives();
+        assert_eq!(game.lives, lives + 3);
+        assert_eq!(game.next_bonus_life, 4000);
+        assert_eq!(game.events, [GameEvent::BonusLife]);
+
+        game.lives = game.config.max_lives - 1;
+        game.score = 9000;
+        game.award_bonus_lives();
+        assert_eq!(game.lives, game.config.max_lives);
+        assert_eq!(game.next_bonus_life, 10000);
+    }
+}
@@ -60,7 +60,260 @@ pub fn test_circle_triangle(center: Vec2D, radius: f64, a: Vec2D, b: Vec2D, c: V
     test_circle_point(center, radius, closest_triangle_point(center, a, b, c))
 }
 
+/// Shortest offset from `from` to `to` on a field of `field_size` whose opposite edges meet.
+pub fn wrapped_delta(from: Vec2D, to: Vec2D, field_size: &Vec2D) -> Vec2D {
+    (to - from).wrap_delta(field_size)
+}
+
+/// Distance between two points, taking the short way around the field edges.
+pub fn wrapped_distance(a: Vec2D, b: Vec2D, field_size: &Vec2D) -> f64 {
+    wrapped_delta(a, b, field_size).len()
+}
+
+/// Returns the copy of `point`, shifted by whole field sizes, that is nearest to `anchor`.
+pub fn nearest_image(anchor: Vec2D, point: Vec2D, field_size: &Vec2D) -> Vec2D {
+    anchor + wrapped_delta(anchor, point, field_size)
+}
+
+/// Like `test_circle_triangle`, for shapes that may touch across the field edges.
+pub fn test_circle_triangle_wrapped(
+    center: Vec2D,
+    radius: f64,
+    triangle: [Vec2D; 3],
+    field_size: &Vec2D,
+) -> bool {
+    let [a, b, c] = triangle;
+    test_circle_triangle(nearest_image(a, center, field_size), radius, a, b, c)
+}
+
+/// Like `test_circle_circle`, for circles that may touch across the field edges.
+pub fn test_circle_circle_wrapped(
+    center_a: Vec2D,
+    radius_a: f64,
+    center_b: Vec2D,
+    radius_b: f64,
+    field_size: &Vec2D,
+) -> bool {
+    wrapped_distance(center_a, center_b, field_size) <= radius_a + radius_b
+}
+
+/// Like `test_circle_point`, for a point that may be across the field edges from the circle.
+pub fn test_circle_point_wrapped(
+    center: Vec2D,
+    radius: f64,
+    point: Vec2D,
+    field_size: &Vec2D,
+) -> bool {
+    test_circle_point(center, radius, nearest_image(center, point, field_size))
+}
+
+/// Calculates the closest point to `p` on the segment `ab`.
+fn closest_segment_point(p: Vec2D, a: Vec2D, b: Vec2D) -> Vec2D {
+    let ab = b - a;
+    let len_squared = ab.len_squared();
+    if len_squared == 0.0 {
+        return a;
+    }
+    a + ab.scale(((p - a).dot(ab) / len_squared).clamp(0.0, 1.0))
+}
+
+/// Iterates over the edges of `polygon`, including the one closing it.
+fn edges(polygon: &[Vec2D]) -> impl Iterator<Item = (Vec2D, Vec2D)> + '_ {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(&a, &b)| (a, b))
+}
+
+/// Calculates whether segments `ab` and `cd` touch or cross.
+fn test_segment_segment(a: Vec2D, b: Vec2D, c: Vec2D, d: Vec2D) -> bool {
+    let (ab, cd) = (b - a, d - c);
+    let (ac_ab, ad_ab) = (ab.cross(c - a), ab.cross(d - a));
+    let (ca_cd, cb_cd) = (cd.cross(a - c), cd.cross(b - c));
+    if ac_ab * ad_ab < 0.0 && ca_cd * cb_cd < 0.0 {
+        return true;
+    }
+    // touching or collinear: some endpoint lies on the other segment
+    let on =
+        |p: Vec2D, a: Vec2D, b: Vec2D| (closest_segment_point(p, a, b) - p).len_squared() == 0.0;
+    on(c, a, b) || on(d, a, b) || on(a, c, d) || on(b, c, d)
+}
+
+/// Calculates whether `point` is inside `polygon`, given as its vertices in order.
+/// The polygon doesn't need to be convex.
+pub fn test_polygon_point(polygon: &[Vec2D], point: Vec2D) -> bool {
+    // count crossings of a ray going from `point` towards +x
+    edges(polygon)
+        .filter(|&(a, b)| {
+            (a.y > point.y) != (b.y > point.y)
+                && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        })
+        .count()
+        % 2
+        == 1
+}
+
+/// Calculates whether two polygons touch or overlap.
+pub fn test_polygon_polygon(a: &[Vec2D], b: &[Vec2D]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    // either the outlines cross, or one polygon is entirely inside the other
+    edges(a).any(|(a0, a1)| edges(b).any(|(b0, b1)| test_segment_segment(a0, a1, b0, b1)))
+        || test_polygon_point(a, b[0])
+        || test_polygon_point(b, a[0])
+}
+
+/// Calculates whether the provided circle (with `center` and `radius`) and `polygon` touch or intersect.
+pub fn test_circle_polygon(center: Vec2D, radius: f64, polygon: &[Vec2D]) -> bool {
+    test_polygon_point(polygon, center)
+        || edges(polygon)
+            .any(|(a, b)| test_circle_point(center, radius, closest_segment_point(center, a, b)))
+}
+
+/// Calculates whether two circles touch or overlap.
+pub fn test_circle_circle(center_a: Vec2D, radius_a: f64, center_b: Vec2D, radius_b: f64) -> bool {
+    test_circle_point(center_a, radius_a + radius_b, center_b)
+}
+
 /// Calculates whether the provided circle (with `center` and `radius`) contains `point`.
 pub fn test_circle_point(center: Vec2D, radius: f64, point: Vec2D) -> bool {
     (center - point).len_squared() <= radius * radius
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vec2D {
+        Vec2D { x, y }
+    }
+
+    #[test]
+    fn wrapped_deltas_take_the_short_way() {
+        let field_size = v(100.0, 50.0);
+        let delta = wrapped_delta(v(95.0, 2.0), v(5.0, 48.0), &field_size);
+        assert_eq!((delta.x, delta.y), (10.0, -4.0));
+        assert_eq!(
+            wrapped_distance(v(1.0, 1.0), v(99.0, 1.0), &field_size),
+            2.0
+        );
+        let image = nearest_image(v(95.0, 2.0), v(5.0, 48.0), &field_size);
+        assert_eq!((image.x, image.y), (105.0, -2.0));
+    }
+
+    #[test]
+    fn wrapped_tests_reach_across_the_edges() {
+        let field_size = v(100.0, 100.0);
+        let triangle = [v(98.0, 50.0), v(110.0, 45.0), v(110.0, 55.0)];
+        assert!(test_circle_triangle_wrapped(
+            v(5.0, 50.0),
+            1.0,
+            triangle,
+            &field_size
+        ));
+        assert!(!test_circle_triangle_wrapped(
+            v(50.0, 50.0),
+            1.0,
+            triangle,
+            &field_size
+        ));
+        assert!(test_circle_circle_wrapped(
+            v(1.0, 1.0),
+            2.0,
+            v(99.0, 99.0),
+            1.0,
+            &field_size
+        ));
+        assert!(test_circle_point_wrapped(
+            v(99.0, 50.0),
+            3.0,
+            v(1.0, 50.0),
+            &field_size
+        ));
+        assert!(!test_circle_point_wrapped(
+            v(90.0, 50.0),
+            3.0,
+            v(1.0, 50.0),
+            &field_size
+        ));
+    }
+
+    /// An L shape, so that the tests cover a concave polygon.
+    fn l_shape() -> [Vec2D; 6] {
+        [
+            v(0.0, 0.0),
+            v(2.0, 0.0),
+            v(2.0, 1.0),
+            v(1.0, 1.0),
+            v(1.0, 2.0),
+            v(0.0, 2.0),
+        ]
+    }
+
+    #[test]
+    fn points_in_concave_polygons() {
+        let shape = l_shape();
+        assert!(test_polygon_point(&shape, v(0.5, 0.5)));
+        assert!(test_polygon_point(&shape, v(0.5, 1.5)));
+        // the notch of the L
+        assert!(!test_polygon_point(&shape, v(1.5, 1.5)));
+        assert!(!test_polygon_point(&shape, v(-0.5, 0.5)));
+        assert!(!test_polygon_point(&[], v(0.0, 0.0)));
+    }
+
+    #[test]
+    fn polygon_overlaps() {
+        let shape = l_shape();
+        let in_notch = [v(1.2, 1.2), v(1.8, 1.2), v(1.5, 1.8)];
+        assert!(!test_polygon_polygon(&shape, &in_notch));
+        let crossing = [v(1.5, 0.5), v(2.5, 0.5), v(2.0, 1.5)];
+        assert!(test_polygon_polygon(&shape, &crossing));
+        // entirely inside, so no edges cross
+        let inside = [v(0.2, 0.2), v(0.8, 0.2), v(0.5, 0.8)];
+        assert!(test_polygon_polygon(&shape, &inside));
+        assert!(test_polygon_polygon(&inside, &shape));
+        // sharing just a corner
+        let touching = [v(2.0, 1.0), v(3.0, 1.0), v(3.0, 2.0)];
+        assert!(test_polygon_polygon(&shape, &touching));
+    }
+
+    #[test]
+    fn segment_crossings() {
+        assert!(test_segment_segment(
+            v(0.0, 0.0),
+            v(2.0, 2.0),
+            v(0.0, 2.0),
+            v(2.0, 0.0)
+        ));
+        assert!(!test_segment_segment(
+            v(0.0, 0.0),
+            v(1.0, 0.0),
+            v(0.0, 1.0),
+            v(1.0, 1.0)
+        ));
+        // collinear and overlapping
+        assert!(test_segment_segment(
+            v(0.0, 0.0),
+            v(2.0, 0.0),
+            v(1.0, 0.0),
+            v(3.0, 0.0)
+        ));
+        assert!(!test_segment_segment(
+            v(0.0, 0.0),
+            v(1.0, 0.0),
+            v(2.0, 0.0),
+            v(3.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn circles_against_polygons() {
+        let shape = l_shape();
+        assert!(test_circle_polygon(v(1.5, 1.5), 0.6, &shape));
+        assert!(!test_circle_polygon(v(1.5, 1.5), 0.4, &shape));
+        assert!(test_circle_polygon(v(0.5, 0.5), 0.1, &shape));
+        assert!(test_circle_circle(v(0.0, 0.0), 1.0, v(3.0, 0.0), 2.0));
+        assert!(!test_circle_circle(v(0.0, 0.0), 1.0, v(3.1, 0.0), 2.0));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::game::Config;
+use crate::math::Vec2D;
 
 /*
  *     The reason we don't use a simple bool for key state is that
@@ -6,7 +7,7 @@ use crate::game::Config;
  * This somehow increases the logic required to handle key presses,
  * but handles instant key presses much better.
  */
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone)]
 enum KeyState {
     Up,
     Down,
@@ -41,26 +42,102 @@ impl KeyState {
     fn is_down(&self) -> bool {
         *self == KeyState::Down
     }
+
+    fn to_bits(self) -> u32 {
+        match self {
+            KeyState::Up => 0,
+            KeyState::Down => 1,
+            KeyState::BeenDown => 2,
+        }
+    }
+
+    fn from_bits(bits: u32) -> Option<KeyState> {
+        match bits {
+            0 => Some(KeyState::Up),
+            1 => Some(KeyState::Down),
+            2 => Some(KeyState::BeenDown),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum InputIndex {
     Shoot = 0,
     Forward = 1,
     Backward = 2,
     Left = 3,
     Right = 4,
-    _NumberOfInputs = 5,
+    Pause = 5,
+    Hyperspace = 6,
+    Shield = 7,
+    _NumberOfInputs = 8,
+}
+
+impl InputIndex {
+    pub fn from_index(index: usize) -> Option<InputIndex> {
+        Some(match index {
+            0 => InputIndex::Shoot,
+            1 => InputIndex::Forward,
+            2 => InputIndex::Backward,
+            3 => InputIndex::Left,
+            4 => InputIndex::Right,
+            5 => InputIndex::Pause,
+            6 => InputIndex::Hyperspace,
+            7 => InputIndex::Shield,
+            _ => return None,
+        })
+    }
+
+    /// The name of the input in settings documents, e.g. `"Shoot"`.
+    pub fn name(self) -> &'static str {
+        INPUT_NAMES[self as usize]
+    }
+
+    pub fn from_name(name: &str) -> Option<InputIndex> {
+        let index = INPUT_NAMES.iter().position(|&known| known == name)?;
+        InputIndex::from_index(index)
+    }
 }
 
+const INPUT_NAMES: [&str; InputIndex::_NumberOfInputs as usize] = [
+    "Shoot",
+    "Forward",
+    "Backward",
+    "Left",
+    "Right",
+    "Pause",
+    "Hyperspace",
+    "Shield",
+];
+
+/// Full analog turn rate, as stored in `Inputs::turn`.
+const TURN_SCALE: f64 = 127.0;
+/// Bit offset of the analog turn rate in `Inputs::to_bits`.
+const TURN_SHIFT: u32 = 24;
+
+#[derive(PartialEq, Eq, Copy, Clone)]
 pub struct Inputs {
     inputs: [KeyState; InputIndex::_NumberOfInputs as usize],
+    /// Analog turn rate from a gamepad stick, from -127 (full left) to 127 (full right).
+    /// It is quantized so that recorded inputs replay exactly.
+    turn: i8,
+    /// Field position the ship turns towards in the mouse aim scheme, in whole units.
+    aim: Option<(u16, u16)>,
+}
+
+impl Default for Inputs {
+    fn default() -> Inputs {
+        Inputs::new()
+    }
 }
 
 impl Inputs {
     pub fn new() -> Inputs {
         Inputs {
             inputs: [KeyState::Up; InputIndex::_NumberOfInputs as usize],
+            turn: 0,
+            aim: None,
         }
     }
 
@@ -72,21 +149,131 @@ impl Inputs {
         self.inputs[idx as usize].is_down()
     }
 
+    /// Analog turn rate, from -1.0 (full left) to 1.0 (full right).
+    pub fn turn(&self) -> f64 {
+        self.turn as f64 / TURN_SCALE
+    }
+
+    pub fn aim(&self) -> Option<Vec2D> {
+        self.aim.map(|(x, y)| Vec2D {
+            x: x as f64,
+            y: y as f64,
+        })
+    }
+
+    /// Sets the aim point, rounded to whole field units so that recorded inputs replay exactly.
+    pub fn set_aim(&mut self, aim: Option<Vec2D>) {
+        let quantize = |coord: f64| coord.round().clamp(0.0, u16::MAX as f64) as u16;
+        self.aim = aim.map(|aim| (quantize(aim.x), quantize(aim.y)));
+    }
+
     pub fn tick(&mut self) {
         for input in self.inputs.iter_mut() {
             input.tick();
         }
     }
 
-    pub fn key_down(&mut self, code: u32, config: &Config) {
+    /// Packs the state of every input into two bits each, and the turn rate into the top byte.
+    pub fn to_bits(&self) -> u32 {
+        let keys = self
+            .inputs
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, input)| bits | (input.to_bits() << (2 * i)));
+        keys | ((self.turn as u8 as u32) << TURN_SHIFT)
+    }
+
+    /// Restores a state packed by `to_bits`.
+    pub fn from_bits(bits: u32) -> Option<Inputs> {
+        let keys = bits & ((1 << TURN_SHIFT) - 1);
+        if keys >> (2 * InputIndex::_NumberOfInputs as usize) != 0 {
+            return None;
+        }
+        let mut inputs = Inputs::new();
+        for (i, input) in inputs.inputs.iter_mut().enumerate() {
+            *input = KeyState::from_bits((keys >> (2 * i)) & 0b11)?;
+        }
+        inputs.turn = (bits >> TURN_SHIFT) as u8 as i8;
+        Some(inputs)
+    }
+
+    /// Holds `idx` down, for input sources that aren't bound through `Config`.
+    pub fn press(&mut self, idx: InputIndex) {
+        self.inputs[idx as usize].down();
+    }
+
+    pub fn release(&mut self, idx: InputIndex) {
+        self.inputs[idx as usize].up();
+    }
+
+    pub fn key_down(&mut self, code: &str, config: &Config) {
         if let Some(index) = config.lookup_input_key(code) {
             self.inputs[index as usize].down();
         }
     }
 
-    pub fn key_up(&mut self, code: u32, config: &Config) {
+    pub fn key_up(&mut self, code: &str, config: &Config) {
         if let Some(index) = config.lookup_input_key(code) {
             self.inputs[index as usize].up();
         }
     }
+
+    pub fn pad_button(&mut self, button: u32, pressed: bool, config: &Config) {
+        if let Some(index) = config.lookup_pad_button(button) {
+            if pressed {
+                self.inputs[index as usize].down();
+            } else {
+                self.inputs[index as usize].up();
+            }
+        }
+    }
+
+    pub fn pad_axis(&mut self, axis: u32, value: f64, config: &Config) {
+        if axis != config.pad_turn_axis {
+            return;
+        }
+        let dead_zone = config.pad_dead_zone;
+        let value = if value.abs() <= dead_zone {
+            0.0
+        } else {
+            value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+        };
+        self.turn = (value.clamp(-1.0, 1.0) * TURN_SCALE).round() as i8;
+    }
+
+    pub fn mouse_move(&mut self, mut pos: Vec2D, config: &Config) {
+        if config.mouse_aim {
+            pos.rem_euclid_assign(&config.field_size);
+            self.set_aim(Some(pos));
+        }
+    }
+
+    /// In the mouse aim scheme, the primary button shoots.
+    pub fn mouse_button(&mut self, button: u32, pressed: bool, config: &Config) {
+        if config.mouse_aim && button == 0 {
+            let shoot = &mut self.inputs[InputIndex::Shoot as usize];
+            if pressed {
+                shoot.down();
+            } else {
+                shoot.up();
+            }
+        }
+    }
+
+    /// Releases every input and centers the turn rate, e.g. when the page loses focus
+    /// and the matching key up events would never arrive.
+    pub fn release_all(&mut self) {
+        for input in self.inputs.iter_mut() {
+            input.up();
+        }
+        self.turn = 0;
+    }
+
+    /// Releases everything a gamepad might be holding down.
+    pub fn pad_disconnected(&mut self, config: &Config) {
+        for &(_, index) in config.pad_binds.iter() {
+            self.inputs[index as usize].up();
+        }
+        self.turn = 0;
+    }
 }
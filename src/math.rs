@@ -86,4 +86,13 @@ impl Vec2D {
         self.x = self.x.rem_euclid(rhs.x);
         self.y = self.y.rem_euclid(rhs.y);
     }
+
+    /// Returns the shortest vector equivalent to `self` on a field of `field_size` that wraps around.
+    pub fn wrap_delta(&self, field_size: &Self) -> Self {
+        let wrap = |d: f64, size: f64| d - size * (d / size).round();
+        Self {
+            x: wrap(self.x, field_size.x),
+            y: wrap(self.y, field_size.y),
+        }
+    }
 }
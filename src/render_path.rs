@@ -1,5 +1,9 @@
-use crate::game::{Asteroid, Bullet, Explosion, Game, InputIndex};
+use crate::game::{
+    Asteroid, Bullet, Explosion, Game, GameState, InputIndex, PowerUp, PowerUpKind, Ufo,
+    POWERUP_RADIUS,
+};
 use crate::math::Vec2D;
+use crate::touch::TouchControls;
 use std::fmt::Write;
 
 mod internals {
@@ -16,7 +20,7 @@ mod internals {
         .expect("could not write string");
     }
 
-    fn draw_points(buf: &mut String, points: &Vec<Vec2D>) {
+    fn draw_points(buf: &mut String, points: &[Vec2D]) {
         if points.is_empty() {
             return;
         }
@@ -48,7 +52,7 @@ mod internals {
         points.iter().map(|&point| point + *translation).collect()
     }
 
-    pub fn draw_points_wrapping(buf: &mut String, points: &Vec<Vec2D>, field_size: &Vec2D) {
+    pub fn draw_points_wrapping(buf: &mut String, points: &[Vec2D], field_size: &Vec2D) {
         let x_wrap = calculate_wrap(points, field_size, true);
         let y_wrap = calculate_wrap(points, field_size, false);
         if x_wrap != 0.0 {
@@ -97,13 +101,24 @@ mod internals {
             &points
                 .iter()
                 .map(|p| p.scale(scale).rotate(rotation) + *offset)
-                .collect(),
+                .collect::<Vec<_>>(),
             field_size,
         );
     }
 }
 use internals::*;
 
+/// Blends a position between ticks, taking the short way around the field edges.
+fn lerp_pos(prev: Vec2D, pos: Vec2D, alpha: f64, field_size: &Vec2D) -> Vec2D {
+    let mut pos = prev + (pos - prev).wrap_delta(field_size).scale(alpha);
+    pos.rem_euclid_assign(field_size);
+    pos
+}
+
+fn lerp(prev: f64, next: f64, alpha: f64) -> f64 {
+    prev + (next - prev) * alpha
+}
+
 const SHIP_POINTS: &[Vec2D] = &[
     Vec2D { x: 10.0, y: 0.0 },
     Vec2D { x: -10.0, y: -5.0 },
@@ -119,72 +134,291 @@ const FLARE: &[Vec2D] = &[
     Vec2D { x: -8.0, y: -1.5 },
 ];
 
-fn render_ship(buf: &mut String, game: &Game) {
+fn render_ship(buf: &mut String, game: &Game, alpha: f64) {
     let ship = &game.ship;
     if ship.dead {
         return;
     }
-    draw_object(
-        buf,
-        SHIP_POINTS,
-        2.0,
-        ship.angle,
-        &ship.pos,
-        &game.config.field_size,
-    );
-    let inputs = &game.inputs;
-    if inputs.is_down(InputIndex::Forward) || inputs.is_down(InputIndex::Backward) {
+    let field_size = &game.config.field_size;
+    let pos = lerp_pos(ship.prev_pos, ship.pos, alpha, field_size);
+    let angle = lerp(ship.prev_angle, ship.angle, alpha);
+    if let Some(jump) = ship.jump {
+        // shrinks away during the first quarter of the jump, and grows back during the last
+        let transit = (jump.end_tick - jump.start_tick).max(1) as f64;
+        let progress = (game.tick as f64 + alpha - jump.start_tick as f64) / transit;
+        if progress < 0.25 {
+            let scale = 2.0 * (1.0 - progress / 0.25);
+            draw_object(buf, SHIP_POINTS, scale, angle, &jump.from, field_size);
+        } else if progress > 0.75 {
+            let scale = 2.0 * (progress - 0.75) / 0.25;
+            draw_object(buf, SHIP_POINTS, scale.min(2.0), angle, &pos, field_size);
+        }
+        return;
+    }
+    // blinks while it can't be destroyed after respawning
+    let invulnerable_left = game.invulnerable_until.saturating_sub(game.tick);
+    if invulnerable_left > 0 && (invulnerable_left / FLASH_PHASE) % 2 == 1 {
+        return;
+    }
+    draw_object(buf, SHIP_POINTS, 2.0, angle, &pos, field_size);
+    if ship.shield {
+        let circle = circle_points(24);
         draw_object(
             buf,
-            FLARE,
-            2.0,
-            ship.angle,
-            &ship.pos,
-            &game.config.field_size,
+            &circle,
+            game.config.shield_radius,
+            angle,
+            &pos,
+            field_size,
         );
     }
+    let inputs = &game.inputs;
+    if inputs.is_down(InputIndex::Forward) || inputs.is_down(InputIndex::Backward) {
+        draw_object(buf, FLARE, 2.0, angle, &pos, field_size);
+    }
 }
 
-fn render_bullet(buf: &mut String, bullet: &Bullet, field_size: &Vec2D) {
-    let tail = bullet.pos + bullet.speed.normalize().scale(5.0);
-    draw_points_wrapping(buf, &vec![bullet.pos, tail], field_size);
+fn render_bullet(buf: &mut String, bullet: &Bullet, alpha: f64, field_size: &Vec2D) {
+    let pos = lerp_pos(bullet.prev_pos, bullet.pos, alpha, field_size);
+    let tail = pos + bullet.speed.normalize().scale(5.0);
+    draw_points_wrapping(buf, &[pos, tail], field_size);
 }
 
-fn render_asteroid(buf: &mut String, asteroid: &Asteroid, field_size: &Vec2D) {
-    let cnt = asteroid.style;
-    let angle = std::f64::consts::TAU / (cnt as f64);
-    let asteroid_points = vec![Vec2D::one(); cnt + 1]
+fn render_asteroid(buf: &mut String, asteroid: &Asteroid, alpha: f64, field_size: &Vec2D) {
+    let outline = asteroid.outline();
+    // close the outline by returning to the first vertex
+    let asteroid_points = outline
         .iter()
-        .enumerate()
-        .map(|(i, v)| v.rotate(angle * (i as f64)))
+        .chain(outline.first())
+        .copied()
         .collect::<Vec<_>>();
     draw_object(
         buf,
         &asteroid_points,
         asteroid.size,
-        asteroid.angle,
-        &asteroid.pos,
+        lerp(asteroid.prev_angle, asteroid.angle, alpha),
+        &lerp_pos(asteroid.prev_pos, asteroid.pos, alpha, field_size),
         field_size,
     );
 }
 
-fn render_lives(buf: &mut String, lives: u64, field_size: &Vec2D) {
+/// Saucer outline for a radius of 1: the hull, then the dome.
+const UFO_POINTS: &[&[Vec2D]] = &[
+    &[
+        Vec2D { x: -1.0, y: 0.0 },
+        Vec2D { x: 1.0, y: 0.0 },
+        Vec2D { x: 0.45, y: 0.35 },
+        Vec2D { x: -0.45, y: 0.35 },
+        Vec2D { x: -1.0, y: 0.0 },
+        Vec2D { x: -0.45, y: -0.3 },
+        Vec2D { x: 0.45, y: -0.3 },
+        Vec2D { x: 1.0, y: 0.0 },
+    ],
+    &[
+        Vec2D { x: -0.45, y: -0.3 },
+        Vec2D { x: -0.25, y: -0.6 },
+        Vec2D { x: 0.25, y: -0.6 },
+        Vec2D { x: 0.45, y: -0.3 },
+    ],
+];
+
+fn render_ufo(buf: &mut String, ufo: &Ufo, alpha: f64, field_size: &Vec2D) {
+    let pos = lerp_pos(ufo.prev_pos, ufo.pos, alpha, field_size);
+    for stroke in UFO_POINTS {
+        draw_object(buf, stroke, ufo.kind.radius(), 0.0, &pos, field_size);
+    }
+}
+
+/// Diamond drawn around power-up glyphs, for a radius of 1.
+const POWERUP_FRAME: &[Vec2D] = &[
+    Vec2D { x: 1.0, y: 0.0 },
+    Vec2D { x: 0.0, y: 1.0 },
+    Vec2D { x: -1.0, y: 0.0 },
+    Vec2D { x: 0.0, y: -1.0 },
+    Vec2D { x: 1.0, y: 0.0 },
+];
+
+/// Strokes of the glyph shown inside a power-up's frame, for a radius of 1.
+fn powerup_glyph(kind: PowerUpKind) -> &'static [&'static [Vec2D]] {
+    match kind {
+        // three shots fanning out
+        PowerUpKind::SpreadShot => &[
+            &[Vec2D { x: -0.5, y: 0.0 }, Vec2D { x: 0.4, y: -0.4 }],
+            &[Vec2D { x: -0.5, y: 0.0 }, Vec2D { x: 0.5, y: 0.0 }],
+            &[Vec2D { x: -0.5, y: 0.0 }, Vec2D { x: 0.4, y: 0.4 }],
+        ],
+        // fast-forward chevrons
+        PowerUpKind::RapidFire => &[
+            &[
+                Vec2D { x: -0.5, y: -0.35 },
+                Vec2D { x: -0.15, y: 0.0 },
+                Vec2D { x: -0.5, y: 0.35 },
+            ],
+            &[
+                Vec2D { x: 0.0, y: -0.35 },
+                Vec2D { x: 0.35, y: 0.0 },
+                Vec2D { x: 0.0, y: 0.35 },
+            ],
+        ],
+        // an arrow through a wall
+        PowerUpKind::Piercing => &[
+            &[Vec2D { x: -0.55, y: 0.0 }, Vec2D { x: 0.55, y: 0.0 }],
+            &[
+                Vec2D { x: 0.3, y: -0.2 },
+                Vec2D { x: 0.55, y: 0.0 },
+                Vec2D { x: 0.3, y: 0.2 },
+            ],
+            &[Vec2D { x: 0.0, y: -0.45 }, Vec2D { x: 0.0, y: 0.45 }],
+        ],
+        // a small ship, like the lives display
+        PowerUpKind::ExtraLife => &[&[
+            Vec2D { x: 0.0, y: -0.5 },
+            Vec2D { x: -0.25, y: 0.5 },
+            Vec2D { x: -0.12, y: 0.4 },
+            Vec2D { x: 0.12, y: 0.4 },
+            Vec2D { x: 0.25, y: 0.5 },
+            Vec2D { x: 0.0, y: -0.5 },
+        ]],
+    }
+}
+
+fn render_powerup_icon(
+    buf: &mut String,
+    kind: PowerUpKind,
+    radius: f64,
+    pos: &Vec2D,
+    field_size: &Vec2D,
+) {
+    draw_object(buf, POWERUP_FRAME, radius, 0.0, pos, field_size);
+    for stroke in powerup_glyph(kind) {
+        draw_object(buf, stroke, radius, 0.0, pos, field_size);
+    }
+}
+
+/// Ticks before a power-up or its effect runs out during which it blinks.
+const EXPIRY_WARNING: u64 = 120;
+
+fn render_powerup(buf: &mut String, game: &Game, powerup: &PowerUp, alpha: f64) {
+    let ticks_left = powerup.lifetime.saturating_sub(game.tick);
+    if ticks_left < EXPIRY_WARNING && (ticks_left / FLASH_PHASE) % 2 == 1 {
+        return;
+    }
+    let field_size = &game.config.field_size;
+    let pos = lerp_pos(powerup.prev_pos, powerup.pos, alpha, field_size);
+    render_powerup_icon(buf, powerup.kind, POWERUP_RADIUS, &pos, field_size);
+}
+
+/// Shows the power-up effects in force below the energy bar, blinking as they run out.
+fn render_effects(buf: &mut String, game: &Game, field_size: &Vec2D) {
+    const EFFECT_STEP: f64 = 30.0;
+    const EFFECT_TOP: f64 = 110.0;
+    let active = PowerUpKind::ALL
+        .into_iter()
+        .filter(|&kind| game.effect_active(kind));
+    for (idx, kind) in active.enumerate() {
+        let ticks_left = game.effect_ends[kind as usize] - game.tick;
+        if ticks_left < EXPIRY_WARNING && (ticks_left / FLASH_PHASE) % 2 == 1 {
+            continue;
+        }
+        let pos = Vec2D {
+            x: 30.0 + (idx as f64) * EFFECT_STEP,
+            y: EFFECT_TOP,
+        };
+        render_powerup_icon(buf, kind, 12.0, &pos, field_size);
+    }
+}
+
+/// Ticks the lives display flashes for after a bonus life.
+const BONUS_LIFE_FLASH: u64 = 120;
+/// Ticks the flashing display stays on, and then off.
+const FLASH_PHASE: u64 = 8;
+
+/// Tops of the players' rows in a two-player game, each with a score and the lives below it.
+const PLAYER_ROWS: [f64; 2] = [50.0, 120.0];
+
+fn render_lives(buf: &mut String, game: &Game, field_size: &Vec2D) {
     const LIFE_STEP: f64 = 40.0;
     const UP_ANGLE: f64 = std::f64::consts::PI * -0.5;
-    for l in 0..lives {
+    let since_bonus = game.bonus_life_tick.map(|tick| game.tick - tick);
+    let flashing =
+        since_bonus.is_some_and(|since| since < BONUS_LIFE_FLASH && (since / FLASH_PHASE) % 2 == 1);
+    if let Some(waiting) = game.waiting_player.as_ref() {
+        // smaller ships, right-aligned under each player's score
+        const SMALL_LIFE_STEP: f64 = 20.0;
+        const SMALL_LIFE_RIGHT: f64 = 1210.0;
+        for (player, top) in PLAYER_ROWS.into_iter().enumerate() {
+            let lives = match player == game.player {
+                true if flashing => continue,
+                true => game.lives,
+                false => waiting.lives,
+            };
+            for l in 0..lives {
+                let x = SMALL_LIFE_RIGHT - (l as f64) * SMALL_LIFE_STEP;
+                let pos = Vec2D { x, y: top + 48.0 };
+                draw_object(buf, SHIP_POINTS, 1.0, UP_ANGLE, &pos, field_size);
+            }
+        }
+        return;
+    }
+    if flashing {
+        return;
+    }
+    for l in 0..game.lives {
         let y = 50.0;
         let x = ((l + 1) as f64) * LIFE_STEP;
         draw_object(buf, SHIP_POINTS, 2.0, UP_ANGLE, &Vec2D { x, y }, field_size);
     }
 }
 
-fn render_explosion(buf: &mut String, explosion: &Explosion, tick: u64, field_size: &Vec2D) {
+/// Draws the shield energy as a bar below the lives, with the charge left as an inner bar.
+fn render_energy(buf: &mut String, energy: f64, field_size: &Vec2D) {
+    const BAR_LEFT: f64 = 20.0;
+    const BAR_TOP: f64 = 75.0;
+    const BAR_WIDTH: f64 = 120.0;
+    const BAR_HEIGHT: f64 = 10.0;
+    const BAR_INSET: f64 = 3.0;
+    let rect = |left: f64, top: f64, width: f64, height: f64| {
+        [
+            Vec2D { x: left, y: top },
+            Vec2D {
+                x: left + width,
+                y: top,
+            },
+            Vec2D {
+                x: left + width,
+                y: top + height,
+            },
+            Vec2D {
+                x: left,
+                y: top + height,
+            },
+            Vec2D { x: left, y: top },
+        ]
+    };
+    draw_points_wrapping(
+        buf,
+        &rect(BAR_LEFT, BAR_TOP, BAR_WIDTH, BAR_HEIGHT),
+        field_size,
+    );
+    let level = (BAR_WIDTH - 2.0 * BAR_INSET) * energy.clamp(0.0, 1.0);
+    if level > 0.0 {
+        let inner = rect(
+            BAR_LEFT + BAR_INSET,
+            BAR_TOP + BAR_INSET,
+            level,
+            BAR_HEIGHT - 2.0 * BAR_INSET,
+        );
+        draw_points_wrapping(buf, &inner, field_size);
+    }
+}
+
+fn render_explosion(buf: &mut String, explosion: &Explosion, tick: f64, field_size: &Vec2D) {
     const EXPLOSION_RADIUS: f64 = 30.0;
     const EXPLOSION_PARTICLES: usize = 11;
     const EXPLOSION_PARTICLE_LENGTH: f64 = 10.0;
     let explosion_da = std::f64::consts::PI * 2.0 / (EXPLOSION_PARTICLES as f64);
-    let state = ((tick - explosion.start_tick) as f64)
-        / ((explosion.lifetime - explosion.start_tick) as f64);
+    let state =
+        (tick - explosion.start_tick as f64) / ((explosion.lifetime - explosion.start_tick) as f64);
 
     for i in 0..EXPLOSION_PARTICLES {
         let a = explosion_da * (i as f64);
@@ -192,7 +426,7 @@ fn render_explosion(buf: &mut String, explosion: &Explosion, tick: u64, field_si
         let start = dir.scale(state * EXPLOSION_RADIUS) + explosion.pos;
         let end = dir.scale(state * EXPLOSION_RADIUS + EXPLOSION_PARTICLE_LENGTH * (1.0 + state))
             + explosion.pos;
-        draw_points_wrapping(buf, &vec![start, end], field_size);
+        draw_points_wrapping(buf, &[start, end], field_size);
     }
 }
 
@@ -290,7 +524,8 @@ const VECTOR_DIGITS: &[&[Vec2D]] = &[
     ],
 ];
 
-fn render_score(buf: &mut String, mut score: u64, field_size: &Vec2D) {
+/// Draws `score` right-aligned, with the top of the digits at `top`.
+fn render_score(buf: &mut String, mut score: u64, top: f64, field_size: &Vec2D) {
     let mut digits = Vec::new();
     while score > 0 {
         digits.push(score % 10);
@@ -306,25 +541,246 @@ fn render_score(buf: &mut String, mut score: u64, field_size: &Vec2D) {
         let digit = VECTOR_DIGITS[*d as usize].to_vec();
         let offset = Vec2D {
             x: DIGIT_RIGHTMOST + (idx as f64) * DIGIT_STEP,
-            y: 5.0,
+            y: top / DIGIT_SCALE,
         }
         .scale(DIGIT_SCALE);
         draw_object(buf, &digit, DIGIT_SCALE, 0.0, &offset, field_size);
     }
 }
 
-pub fn render_game(buf: &mut String, game: &Game) {
+/// Shows the score, or in a two-player game a labelled row per player,
+/// with an arrow by the player whose turn it is.
+fn render_scores(buf: &mut String, game: &Game, field_size: &Vec2D) {
+    const LABEL_X: f64 = 880.0;
+    const MARKER_X: f64 = 840.0;
+    let Some(waiting) = game.waiting_player.as_ref() else {
+        render_score(buf, game.score, 50.0, field_size);
+        return;
+    };
+    for (player, top) in PLAYER_ROWS.into_iter().enumerate() {
+        let current = player == game.player;
+        let score = if current { game.score } else { waiting.score };
+        render_score(buf, score, top, field_size);
+        let middle = top + 15.0;
+        let label = if player == 0 { "P1" } else { "P2" };
+        let label_pos = Vec2D {
+            x: LABEL_X,
+            y: middle,
+        };
+        render_text(buf, label, label_pos, 8.0, field_size);
+        if current {
+            let marker_pos = Vec2D {
+                x: MARKER_X,
+                y: middle,
+            };
+            draw_object(buf, ARROW, 16.0, 0.0, &marker_pos, field_size);
+        }
+    }
+}
+
+/// Strokes of the letters used in on-screen messages, on the same grid as `VECTOR_DIGITS`.
+fn vector_letter(chr: char) -> &'static [&'static [Vec2D]] {
+    match chr {
+        'A' => &[
+            &[
+                Vec2D { x: 0.0, y: 3.0 },
+                Vec2D { x: 0.0, y: 1.0 },
+                Vec2D { x: 1.0, y: 0.0 },
+                Vec2D { x: 2.0, y: 1.0 },
+                Vec2D { x: 2.0, y: 3.0 },
+            ],
+            &[Vec2D { x: 0.0, y: 2.0 }, Vec2D { x: 2.0, y: 2.0 }],
+        ],
+        'D' => &[&[
+            Vec2D { x: 0.0, y: 0.0 },
+            Vec2D { x: 1.0, y: 0.0 },
+            Vec2D { x: 2.0, y: 1.0 },
+            Vec2D { x: 2.0, y: 2.0 },
+            Vec2D { x: 1.0, y: 3.0 },
+            Vec2D { x: 0.0, y: 3.0 },
+            Vec2D { x: 0.0, y: 0.0 },
+        ]],
+        'E' => &[
+            &[
+                Vec2D { x: 2.0, y: 0.0 },
+                Vec2D { x: 0.0, y: 0.0 },
+                Vec2D { x: 0.0, y: 3.0 },
+                Vec2D { x: 2.0, y: 3.0 },
+            ],
+            &[Vec2D { x: 0.0, y: 1.5 }, Vec2D { x: 1.5, y: 1.5 }],
+        ],
+        'L' => &[&[
+            Vec2D { x: 0.0, y: 0.0 },
+            Vec2D { x: 0.0, y: 3.0 },
+            Vec2D { x: 2.0, y: 3.0 },
+        ]],
+        'P' => &[&[
+            Vec2D { x: 0.0, y: 3.0 },
+            Vec2D { x: 0.0, y: 0.0 },
+            Vec2D { x: 2.0, y: 0.0 },
+            Vec2D { x: 2.0, y: 1.5 },
+            Vec2D { x: 0.0, y: 1.5 },
+        ]],
+        'R' => &[&[
+            Vec2D { x: 0.0, y: 3.0 },
+            Vec2D { x: 0.0, y: 0.0 },
+            Vec2D { x: 2.0, y: 0.0 },
+            Vec2D { x: 2.0, y: 1.5 },
+            Vec2D { x: 0.0, y: 1.5 },
+            Vec2D { x: 2.0, y: 3.0 },
+        ]],
+        'S' => &[&[
+            Vec2D { x: 2.0, y: 0.0 },
+            Vec2D { x: 0.0, y: 0.0 },
+            Vec2D { x: 0.0, y: 1.5 },
+            Vec2D { x: 2.0, y: 1.5 },
+            Vec2D { x: 2.0, y: 3.0 },
+            Vec2D { x: 0.0, y: 3.0 },
+        ]],
+        'U' => &[&[
+            Vec2D { x: 0.0, y: 0.0 },
+            Vec2D { x: 0.0, y: 3.0 },
+            Vec2D { x: 2.0, y: 3.0 },
+            Vec2D { x: 2.0, y: 0.0 },
+        ]],
+        'Y' => &[
+            &[
+                Vec2D { x: 0.0, y: 0.0 },
+                Vec2D { x: 1.0, y: 1.5 },
+                Vec2D { x: 2.0, y: 0.0 },
+            ],
+            &[Vec2D { x: 1.0, y: 1.5 }, Vec2D { x: 1.0, y: 3.0 }],
+        ],
+        _ => &[],
+    }
+}
+
+/// Draws `text` centered on `center`, skipping characters without a vector shape.
+fn render_text(buf: &mut String, text: &str, center: Vec2D, scale: f64, field_size: &Vec2D) {
+    const LETTER_STEP: f64 = 3.0;
+    let width = (text.chars().count() as f64) * LETTER_STEP - 1.0;
+    let top_left = center
+        - Vec2D {
+            x: width * 0.5,
+            y: 1.5,
+        }
+        .scale(scale);
+    for (idx, chr) in text.chars().enumerate() {
+        let offset = top_left
+            + Vec2D {
+                x: (idx as f64) * LETTER_STEP * scale,
+                y: 0.0,
+            };
+        let strokes = match chr.to_digit(10) {
+            Some(digit) => std::slice::from_ref(&VECTOR_DIGITS[digit as usize]),
+            None => vector_letter(chr),
+        };
+        for stroke in strokes {
+            draw_object(buf, stroke, scale, 0.0, &offset, field_size);
+        }
+    }
+}
+
+const ARROW: &[Vec2D] = &[
+    Vec2D { x: -0.5, y: -0.6 },
+    Vec2D { x: 0.5, y: 0.0 },
+    Vec2D { x: -0.5, y: 0.6 },
+    Vec2D { x: -0.5, y: -0.6 },
+];
+
+const CROSSHAIR: &[&[Vec2D]] = &[
+    &[Vec2D { x: -0.6, y: 0.0 }, Vec2D { x: 0.6, y: 0.0 }],
+    &[Vec2D { x: 0.0, y: -0.6 }, Vec2D { x: 0.0, y: 0.6 }],
+];
+
+fn circle_points(sides: usize) -> Vec<Vec2D> {
+    let angle = std::f64::consts::TAU / (sides as f64);
+    (0..=sides)
+        .map(|i| Vec2D::one().rotate(angle * (i as f64)))
+        .collect()
+}
+
+/// Draws the touch zones as outlines, with a doubled outline for zones being held.
+pub fn render_touch_controls(buf: &mut String, controls: &TouchControls, field_size: &Vec2D) {
+    const UP_ANGLE: f64 = std::f64::consts::PI * -0.5;
+    if !controls.visible {
+        return;
+    }
+    let circle = circle_points(24);
+    for (idx, zone) in controls.zones.iter().enumerate() {
+        draw_object(buf, &circle, zone.radius, 0.0, &zone.center, field_size);
+        if controls.is_pressed(idx) {
+            draw_object(
+                buf,
+                &circle,
+                zone.radius - 6.0,
+                0.0,
+                &zone.center,
+                field_size,
+            );
+        }
+        let glyph_size = zone.radius * 0.5;
+        let arrow_angle = match zone.input {
+            InputIndex::Left => Some(std::f64::consts::PI),
+            InputIndex::Right => Some(0.0),
+            InputIndex::Forward => Some(UP_ANGLE),
+            _ => None,
+        };
+        match arrow_angle {
+            Some(angle) => draw_object(buf, ARROW, glyph_size, angle, &zone.center, field_size),
+            None => {
+                for line in CROSSHAIR {
+                    draw_object(buf, line, glyph_size, 0.0, &zone.center, field_size);
+                }
+            }
+        }
+    }
+}
+
+/// Renders the game `alpha` of the way from the previous tick to the current one.
+pub fn render_game(buf: &mut String, game: &Game, alpha: f64) {
     let field_size = game.config.field_size;
-    render_lives(buf, game.lives, &field_size);
-    render_ship(buf, game);
+    // paused and finished games don't tick, so blending would keep replaying the last tick
+    let alpha = if matches!(game.game_state, GameState::Paused | GameState::GameOver) {
+        1.0
+    } else {
+        alpha
+    };
+    render_lives(buf, game, &field_size);
+    render_energy(buf, game.ship.energy, &field_size);
+    render_effects(buf, game, &field_size);
+    render_ship(buf, game, alpha);
+    if game.game_state == GameState::Respawning && game.lives > 0 && !game.spawn_clear() {
+        // mark out the area that has to clear before the ship can respawn
+        let circle = circle_points(24);
+        let center = field_size.scale(0.5);
+        let radius = game.config.respawn_clearance;
+        draw_object(buf, &circle, radius, 0.0, &center, &field_size);
+    }
     for bullet in game.bullets.iter() {
-        render_bullet(buf, bullet, &field_size);
+        render_bullet(buf, bullet, alpha, &field_size);
     }
     for asteroid in game.asteroids.iter() {
-        render_asteroid(buf, asteroid, &field_size);
+        render_asteroid(buf, asteroid, alpha, &field_size);
+    }
+    if let Some(ufo) = game.ufo.as_ref() {
+        render_ufo(buf, ufo, alpha, &field_size);
     }
+    for powerup in game.powerups.iter() {
+        render_powerup(buf, game, powerup, alpha);
+    }
+    let tick = game.tick as f64 + alpha;
     for explosion in game.explosions.iter() {
-        render_explosion(buf, explosion, game.tick, &field_size);
+        render_explosion(buf, explosion, tick, &field_size);
+    }
+    render_scores(buf, game, &field_size);
+    if game.waiting_player.is_some() && game.game_state == GameState::Respawning && game.lives > 0 {
+        // whose turn it is, above the respawn area
+        let text = format!("PLAYER {}", game.player + 1);
+        let pos = field_size.scale(0.5) - Vec2D { x: 0.0, y: 150.0 };
+        render_text(buf, &text, pos, 20.0, &field_size);
+    }
+    if game.game_state == GameState::Paused {
+        render_text(buf, "PAUSED", field_size.scale(0.5), 20.0, &field_size);
     }
-    render_score(buf, game.score, &field_size);
 }
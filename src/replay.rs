@@ -0,0 +1,251 @@
+use crate::game::{Config, Game, Inputs};
+use crate::serialize::{DecodeError, Encode, Reader, Writer};
+
+const MAGIC: &[u8; 4] = b"ASTR";
+/// Bump this whenever the layout of the replay or of `Config` changes.
+const VERSION: u8 = 12;
+/// Longest replay that is loaded, about four hours at the default 60 ticks per second,
+/// so that a corrupt run length can't exhaust memory.
+const MAX_TICKS: usize = 4 * 60 * 60 * 60;
+
+/// A recorded game: everything needed to play it back tick by tick.
+///
+/// The per-tick input states are stored run-length encoded,
+/// since they stay the same for long stretches of play.
+#[derive(Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: Config,
+    ticks: Vec<Inputs>,
+}
+
+impl Replay {
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.bytes(MAGIC);
+        w.u8(VERSION);
+        w.u64(self.seed);
+        self.config.encode(&mut w);
+
+        let mut runs = Vec::new();
+        for inputs in self.ticks.iter() {
+            match runs.last_mut() {
+                Some((last, count)) if last == inputs => *count += 1,
+                _ => runs.push((*inputs, 1usize)),
+            }
+        }
+        w.usize(runs.len());
+        for (inputs, count) in runs {
+            w.usize(count);
+            inputs.encode(&mut w);
+        }
+        w.into_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, DecodeError> {
+        let mut r = Reader::new(bytes);
+        if r.bytes(MAGIC.len())? != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        let version = r.u8()?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let seed = r.u64()?;
+        let config = Config::decode(&mut r)?;
+
+        let mut ticks = Vec::new();
+        for _ in 0..r.usize()? {
+            let count = r.usize()?;
+            let inputs = Inputs::decode(&mut r)?;
+            let len = ticks
+                .len()
+                .checked_add(count)
+                .filter(|&len| len <= MAX_TICKS)
+                .ok_or(DecodeError::InvalidValue)?;
+            ticks.resize(len, inputs);
+        }
+        if !r.is_empty() {
+            return Err(DecodeError::InvalidValue);
+        }
+        Ok(Replay {
+            seed,
+            config,
+            ticks,
+        })
+    }
+}
+
+/// Records the input state of every tick of a game from its start.
+pub struct Recorder {
+    replay: Replay,
+}
+
+impl Recorder {
+    /// Starts recording `game`, which must not have been ticked yet.
+    ///
+    /// Panics if it has, since playback starts from a fresh game and would go its own way.
+    pub fn new(game: &Game) -> Recorder {
+        assert_eq!(game.tick, 0, "recording has to start with the game");
+        Recorder {
+            replay: Replay {
+                seed: game.seed(),
+                config: game.config.clone(),
+                ticks: Vec::new(),
+            },
+        }
+    }
+
+    /// Records the inputs `game` is about to be ticked with.
+    /// Call this right before every `Game::tick`.
+    pub fn record(&mut self, game: &Game) {
+        self.replay.ticks.push(game.inputs);
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+/// Drives a game from a recorded input stream instead of live key events.
+pub struct Playback {
+    replay: Replay,
+    position: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        Playback {
+            replay,
+            position: 0,
+        }
+    }
+
+    /// Creates the game the replay was recorded from.
+    pub fn new_game(&self) -> Game {
+        Game::with_config(self.replay.seed, self.replay.config.clone())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.replay.ticks.len()
+    }
+
+    /// Feeds the next recorded tick into `game`.
+    /// Returns `false` once the recording is exhausted.
+    pub fn step(&mut self, game: &mut Game) -> bool {
+        match self.replay.ticks.get(self.position) {
+            Some(inputs) => {
+                game.inputs = *inputs;
+                game.tick();
+                self.position += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::{assert_same, script};
+    use crate::game::InputIndex;
+    use crate::serialize::Writer;
+
+    /// Plays `ticks` ticks of scripted input from a fresh game, recording them.
+    fn record(ticks: u64) -> (Game, Replay) {
+        let mut game = Game::with_seed(3);
+        let mut recorder = Recorder::new(&game);
+        for _ in 0..ticks {
+            script(&mut game);
+            recorder.record(&game);
+            game.tick();
+        }
+        (game, recorder.finish())
+    }
+
+    #[test]
+    fn replay_bytes_round_trip() {
+        let (_, replay) = record(600);
+        let bytes = replay.to_bytes();
+        let loaded = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.len(), 600);
+        assert_eq!(loaded.to_bytes(), bytes);
+        assert!(matches!(
+            Replay::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn playback_reproduces_the_recorded_game() {
+        let (game, replay) = record(600);
+        let mut playback = Playback::new(Replay::from_bytes(&replay.to_bytes()).unwrap());
+        let mut replayed = playback.new_game();
+        while playback.step(&mut replayed) {}
+        assert!(playback.is_finished());
+        assert_same(&replayed, &game);
+    }
+
+    #[test]
+    #[should_panic]
+    fn recordings_start_with_the_game() {
+        let mut game = Game::with_seed(3);
+        game.tick();
+        Recorder::new(&game);
+    }
+
+    #[test]
+    fn oversized_runs_are_rejected() {
+        let (_, replay) = record(10);
+        let mut w = Writer::new();
+        w.bytes(MAGIC);
+        w.u8(VERSION);
+        w.u64(replay.seed);
+        replay.config.encode(&mut w);
+        w.usize(2);
+        for _ in 0..2 {
+            w.usize(usize::MAX - 1);
+            Inputs::new().encode(&mut w);
+        }
+        assert!(matches!(
+            Replay::from_bytes(&w.into_bytes()),
+            Err(DecodeError::InvalidValue)
+        ));
+    }
+
+    #[test]
+    fn pauses_play_back() {
+        let mut game = Game::with_seed(3);
+        let mut recorder = Recorder::new(&game);
+        for step in 0..300 {
+            match step {
+                100 => game.request_pause(),
+                200 => game.inputs.press(InputIndex::Pause),
+                201 => game.inputs.release(InputIndex::Pause),
+                _ => script(&mut game),
+            }
+            recorder.record(&game);
+            game.tick();
+        }
+        // the hundred ticks in between were spent paused
+        assert_eq!(game.tick, 200);
+
+        let mut playback = Playback::new(recorder.finish());
+        let mut replayed = playback.new_game();
+        while playback.step(&mut replayed) {}
+        assert_same(&replayed, &game);
+    }
+}
@@ -1,13 +1,138 @@
-use rand::SeedableRng;
-pub use rand::{rngs::SmallRng, Rng};
-use web_sys::window;
-
-pub fn new_rng() -> Option<SmallRng> {
-    let mut seed = [0u8; 16];
-    window()?
-        .crypto()
-        .ok()?
-        .get_random_values_with_u8_array(&mut seed)
-        .ok()?;
-    Some(SmallRng::from_seed(seed))
+pub use rand::Rng;
+use rand::{Error, RngCore, SeedableRng};
+
+/// A source of random bytes used to seed the game RNG.
+pub trait Entropy {
+    /// Fills `buf` with random bytes, returning `None` if no entropy is available.
+    fn fill(&mut self, buf: &mut [u8]) -> Option<()>;
+}
+
+/// Entropy provided by `window.crypto.getRandomValues`.
+#[cfg(feature = "web")]
+pub struct WebCrypto;
+
+#[cfg(feature = "web")]
+impl Entropy for WebCrypto {
+    fn fill(&mut self, buf: &mut [u8]) -> Option<()> {
+        web_sys::window()?
+            .crypto()
+            .ok()?
+            .get_random_values_with_u8_array(buf)
+            .ok()?;
+        Some(())
+    }
+}
+
+pub fn new_seed(entropy: &mut impl Entropy) -> Option<u64> {
+    let mut seed = [0u8; 8];
+    entropy.fill(&mut seed)?;
+    Some(u64::from_le_bytes(seed))
+}
+
+/// Xoshiro128++, the same generator `rand::rngs::SmallRng` uses on 32-bit targets.
+///
+/// `SmallRng` picks a different algorithm depending on pointer width,
+/// so a seed recorded in the browser would play out differently in a native build.
+/// This one produces the same sequence everywhere.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRng {
+    s: [u32; 4],
+}
+
+impl GameRng {
+    pub fn from_u64(seed: u64) -> GameRng {
+        GameRng::seed_from_u64(seed)
+    }
+
+    /// The internal state, for saving the generator mid-game.
+    pub fn state(&self) -> [u32; 4] {
+        self.s
+    }
+
+    /// Restores a generator from `state`, which may not be all zeroes.
+    pub fn from_state(s: [u32; 4]) -> Option<GameRng> {
+        if s == [0; 4] {
+            return None;
+        }
+        Some(GameRng { s })
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        let s = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(7).wrapping_add(s[0]);
+        let t = s[1] << 9;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(11);
+        result
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let lo = self.next_u32() as u64;
+        let hi = self.next_u32() as u64;
+        (hi << 32) | lo
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for GameRng {
+    type Seed = [u8; 16];
+
+    fn from_seed(seed: [u8; 16]) -> GameRng {
+        let mut s = [0u32; 4];
+        for (word, bytes) in s.iter_mut().zip(seed.chunks(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        if s == [0; 4] {
+            // the all-zero state is a fixed point of xoshiro
+            return GameRng::seed_from_u64(0);
+        }
+        GameRng { s }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_give_fixed_sequences() {
+        let mut rng = GameRng::from_u64(1);
+        let first: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+        let mut again = GameRng::from_u64(1);
+        assert!(first.iter().all(|&value| value == again.next_u32()));
+        let mut other = GameRng::from_u64(2);
+        assert!(first.iter().any(|&value| value != other.next_u32()));
+    }
+
+    #[test]
+    fn zero_seeds_still_generate() {
+        let mut rng = GameRng::from_seed([0; 16]);
+        assert!((0..4).any(|_| rng.next_u32() != 0));
+    }
+
+    #[test]
+    fn saved_state_continues_the_sequence() {
+        let mut rng = GameRng::from_u64(7);
+        rng.next_u64();
+        let mut restored = GameRng::from_state(rng.state()).unwrap();
+        assert_eq!(restored.next_u64(), rng.next_u64());
+        assert_eq!(GameRng::from_state([0; 4]), None);
+    }
 }
@@ -0,0 +1,704 @@
+use crate::config::AsteroidSettings;
+use crate::game::{
+    Asteroid, AsteroidSize, Bullet, BulletSource, Config, Explosion, GameState, InputIndex, Inputs,
+    PlayerState, PowerUp, PowerUpKind, Ufo, UfoKind, ASTEROID_SHAPES,
+};
+use crate::math::Vec2D;
+use crate::rng::GameRng;
+use crate::ship::{Jump, Ship};
+use std::fmt;
+
+/// Highest level a saved game may be at. Real games never get close to it,
+/// and `Game::spawn_level` spawns as many asteroids as the level number.
+pub(crate) const MAX_LEVEL: usize = 1000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The data does not start with the expected magic bytes.
+    BadMagic,
+    /// The data was written by an unknown version of the format.
+    UnsupportedVersion(u8),
+    /// The data ended in the middle of a value.
+    UnexpectedEof,
+    /// A value was read, but it is out of range for its type.
+    InvalidValue,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "not a recognized file"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of data"),
+            DecodeError::InvalidValue => write!(f, "invalid value"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Little-endian byte writer.
+#[derive(Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Writer {
+        Writer::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    pub fn u16(&mut self, val: u16) {
+        self.bytes(&val.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, val: u64) {
+        self.bytes(&val.to_le_bytes());
+    }
+
+    pub fn f64(&mut self, val: f64) {
+        self.bytes(&val.to_le_bytes());
+    }
+
+    pub fn bool(&mut self, val: bool) {
+        self.u8(val as u8);
+    }
+
+    /// Writes `val` as LEB128, which takes a single byte for values below 128.
+    pub fn varint(&mut self, mut val: u64) {
+        while val >= 0x80 {
+            self.u8((val as u8) | 0x80);
+            val >>= 7;
+        }
+        self.u8(val as u8);
+    }
+
+    pub fn usize(&mut self, val: usize) {
+        self.varint(val as u64);
+    }
+
+    pub fn str(&mut self, val: &str) {
+        self.usize(val.len());
+        self.bytes(val.as_bytes());
+    }
+}
+
+/// Little-endian byte reader, the counterpart of `Writer`.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Reader<'a> {
+        Reader { buf }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.buf.len() < len {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, DecodeError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    pub fn bool(&mut self) -> Result<bool, DecodeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+
+    pub fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut val = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            val |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(val);
+            }
+        }
+        Err(DecodeError::InvalidValue)
+    }
+
+    /// Reads a `u32` written with `Writer::varint`.
+    pub fn varint_u32(&mut self) -> Result<u32, DecodeError> {
+        self.varint()?
+            .try_into()
+            .map_err(|_| DecodeError::InvalidValue)
+    }
+
+    pub fn usize(&mut self) -> Result<usize, DecodeError> {
+        self.varint()?
+            .try_into()
+            .map_err(|_| DecodeError::InvalidValue)
+    }
+
+    pub fn str(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.usize()?;
+        std::str::from_utf8(self.bytes(len)?).map_err(|_| DecodeError::InvalidValue)
+    }
+}
+
+/// A value that can be written to and read back from the binary formats.
+pub trait Encode: Sized {
+    fn encode(&self, w: &mut Writer);
+    fn decode(r: &mut Reader) -> Result<Self, DecodeError>;
+}
+
+impl Encode for Vec2D {
+    fn encode(&self, w: &mut Writer) {
+        w.f64(self.x);
+        w.f64(self.y);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Vec2D, DecodeError> {
+        Ok(Vec2D {
+            x: r.f64()?,
+            y: r.f64()?,
+        })
+    }
+}
+
+impl Encode for InputIndex {
+    fn encode(&self, w: &mut Writer) {
+        w.u8(*self as u8);
+    }
+
+    fn decode(r: &mut Reader) -> Result<InputIndex, DecodeError> {
+        InputIndex::from_index(r.u8()? as usize).ok_or(DecodeError::InvalidValue)
+    }
+}
+
+impl Encode for Inputs {
+    fn encode(&self, w: &mut Writer) {
+        w.varint(self.to_bits() as u64);
+        self.aim().map(|aim| [aim.x as u16, aim.y as u16]).encode(w);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Inputs, DecodeError> {
+        let mut inputs = Inputs::from_bits(r.varint_u32()?).ok_or(DecodeError::InvalidValue)?;
+        let aim = Option::<[u16; 2]>::decode(r)?;
+        inputs.set_aim(aim.map(|[x, y]| Vec2D {
+            x: x as f64,
+            y: y as f64,
+        }));
+        Ok(inputs)
+    }
+}
+
+impl Encode for [u16; 2] {
+    fn encode(&self, w: &mut Writer) {
+        w.u16(self[0]);
+        w.u16(self[1]);
+    }
+
+    fn decode(r: &mut Reader) -> Result<[u16; 2], DecodeError> {
+        Ok([r.u16()?, r.u16()?])
+    }
+}
+
+impl Encode for AsteroidSettings {
+    fn encode(&self, w: &mut Writer) {
+        w.f64(self.radius);
+        w.varint(self.score);
+        w.varint(self.fragments);
+        w.f64(self.min_speed);
+        w.f64(self.max_speed);
+    }
+
+    fn decode(r: &mut Reader) -> Result<AsteroidSettings, DecodeError> {
+        Ok(AsteroidSettings {
+            radius: r.f64()?,
+            score: r.varint()?,
+            fragments: r.varint()?,
+            min_speed: r.f64()?,
+            max_speed: r.f64()?,
+        })
+    }
+}
+
+impl Encode for AsteroidSize {
+    fn encode(&self, w: &mut Writer) {
+        w.u8(*self as u8);
+    }
+
+    fn decode(r: &mut Reader) -> Result<AsteroidSize, DecodeError> {
+        match r.u8()? {
+            0 => Ok(AsteroidSize::Large),
+            1 => Ok(AsteroidSize::Medium),
+            2 => Ok(AsteroidSize::Small),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+}
+
+impl Encode for Config {
+    fn encode(&self, w: &mut Writer) {
+        w.f64(self.acceleration);
+        w.f64(self.speed_limit);
+        w.f64(self.drag);
+        w.f64(self.explosion_life);
+        w.f64(self.angular_accel);
+        w.f64(self.angular_limit);
+        w.f64(self.angular_drag);
+        w.f64(self.bullet_interval);
+        w.f64(self.bullet_speed);
+        w.f64(self.bullet_lifetime);
+        w.f64(self.delta_t);
+        for settings in self.asteroid_sizes.iter() {
+            settings.encode(w);
+        }
+        w.f64(self.ufo_interval);
+        w.f64(self.ufo_speed);
+        w.f64(self.ufo_fire_interval);
+        w.varint(self.bonus_life_score);
+        w.varint(self.max_lives);
+        w.f64(self.hyperspace_transit);
+        w.f64(self.hyperspace_cooldown);
+        w.f64(self.hyperspace_failure);
+        w.f64(self.shield_radius);
+        w.f64(self.shield_drain);
+        w.f64(self.shield_recharge);
+        w.f64(self.powerup_chance);
+        w.f64(self.powerup_speed);
+        w.f64(self.powerup_lifetime);
+        w.f64(self.powerup_duration);
+        w.f64(self.rapid_fire_interval);
+        w.f64(self.respawn_clearance);
+        w.f64(self.respawn_invulnerability);
+        self.field_size.encode(w);
+        w.usize(self.key_binds.len());
+        for (code, index) in self.key_binds.iter() {
+            w.str(code);
+            index.encode(w);
+        }
+        w.usize(self.pad_binds.len());
+        for (button, index) in self.pad_binds.iter() {
+            w.varint(*button as u64);
+            index.encode(w);
+        }
+        w.varint(self.pad_turn_axis as u64);
+        w.f64(self.pad_dead_zone);
+        w.bool(self.mouse_aim);
+        w.bool(self.two_players);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Config, DecodeError> {
+        let mut config = Config::new();
+        config.acceleration = r.f64()?;
+        config.speed_limit = r.f64()?;
+        config.drag = r.f64()?;
+        config.explosion_life = r.f64()?;
+        config.angular_accel = r.f64()?;
+        config.angular_limit = r.f64()?;
+        config.angular_drag = r.f64()?;
+        config.bullet_interval = r.f64()?;
+        config.bullet_speed = r.f64()?;
+        config.bullet_lifetime = r.f64()?;
+        config.delta_t = r.f64()?;
+        for settings in config.asteroid_sizes.iter_mut() {
+            *settings = AsteroidSettings::decode(r)?;
+        }
+        config.ufo_interval = r.f64()?;
+        config.ufo_speed = r.f64()?;
+        config.ufo_fire_interval = r.f64()?;
+        config.bonus_life_score = r.varint()?;
+        config.max_lives = r.varint()?;
+        config.hyperspace_transit = r.f64()?;
+        config.hyperspace_cooldown = r.f64()?;
+        config.hyperspace_failure = r.f64()?;
+        config.shield_radius = r.f64()?;
+        config.shield_drain = r.f64()?;
+        config.shield_recharge = r.f64()?;
+        config.powerup_chance = r.f64()?;
+        config.powerup_speed = r.f64()?;
+        config.powerup_lifetime = r.f64()?;
+        config.powerup_duration = r.f64()?;
+        config.rapid_fire_interval = r.f64()?;
+        config.respawn_clearance = r.f64()?;
+        config.respawn_invulnerability = r.f64()?;
+        config.field_size = Vec2D::decode(r)?;
+        let len = r.usize()?;
+        config.key_binds.clear();
+        for _ in 0..len {
+            let code = r.str()?.to_string();
+            config.key_binds.push((code, InputIndex::decode(r)?));
+        }
+        let len = r.usize()?;
+        config.pad_binds.clear();
+        for _ in 0..len {
+            let button = r.varint_u32()?;
+            config.pad_binds.push((button, InputIndex::decode(r)?));
+        }
+        config.pad_turn_axis = r.varint_u32()?;
+        config.pad_dead_zone = r.f64()?;
+        config.mouse_aim = r.bool()?;
+        config.two_players = r.bool()?;
+        // saves and replays are shared around, so they get the same checks as settings text
+        config.validate().map_err(|_| DecodeError::InvalidValue)?;
+        Ok(config)
+    }
+}
+
+impl Encode for u64 {
+    fn encode(&self, w: &mut Writer) {
+        w.u64(*self);
+    }
+
+    fn decode(r: &mut Reader) -> Result<u64, DecodeError> {
+        r.u64()
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, w: &mut Writer) {
+        w.usize(self.len());
+        for item in self.iter() {
+            item.encode(w);
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Vec<T>, DecodeError> {
+        let len = r.usize()?;
+        let mut items = Vec::new();
+        for _ in 0..len {
+            items.push(T::decode(r)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, w: &mut Writer) {
+        w.bool(self.is_some());
+        if let Some(item) = self {
+            item.encode(w);
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<Option<T>, DecodeError> {
+        Ok(if r.bool()? { Some(T::decode(r)?) } else { None })
+    }
+}
+
+impl Encode for GameRng {
+    fn encode(&self, w: &mut Writer) {
+        for word in self.state() {
+            w.bytes(&word.to_le_bytes());
+        }
+    }
+
+    fn decode(r: &mut Reader) -> Result<GameRng, DecodeError> {
+        let mut state = [0u32; 4];
+        for word in state.iter_mut() {
+            *word = u32::from_le_bytes(r.bytes(4)?.try_into().unwrap());
+        }
+        GameRng::from_state(state).ok_or(DecodeError::InvalidValue)
+    }
+}
+
+impl Encode for GameState {
+    fn encode(&self, w: &mut Writer) {
+        w.u8(match self {
+            GameState::Running => 0,
+            GameState::Respawning => 1,
+            GameState::GameOver => 2,
+            GameState::Paused => 3,
+        });
+    }
+
+    fn decode(r: &mut Reader) -> Result<GameState, DecodeError> {
+        match r.u8()? {
+            0 => Ok(GameState::Running),
+            1 => Ok(GameState::Respawning),
+            2 => Ok(GameState::GameOver),
+            3 => Ok(GameState::Paused),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+}
+
+impl Encode for Ship {
+    fn encode(&self, w: &mut Writer) {
+        self.pos.encode(w);
+        self.prev_pos.encode(w);
+        self.speed.encode(w);
+        w.bool(self.dead);
+        w.f64(self.angle);
+        w.f64(self.prev_angle);
+        w.f64(self.angular_speed);
+        self.jump.encode(w);
+        w.bool(self.shield);
+        w.f64(self.energy);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Ship, DecodeError> {
+        Ok(Ship {
+            pos: Vec2D::decode(r)?,
+            prev_pos: Vec2D::decode(r)?,
+            speed: Vec2D::decode(r)?,
+            dead: r.bool()?,
+            angle: r.f64()?,
+            prev_angle: r.f64()?,
+            angular_speed: r.f64()?,
+            jump: Option::decode(r)?,
+            shield: r.bool()?,
+            energy: r.f64()?,
+        })
+    }
+}
+
+impl Encode for Jump {
+    fn encode(&self, w: &mut Writer) {
+        self.from.encode(w);
+        w.u64(self.start_tick);
+        w.u64(self.end_tick);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Jump, DecodeError> {
+        let from = Vec2D::decode(r)?;
+        let start_tick = r.u64()?;
+        Ok(Jump {
+            from,
+            start_tick,
+            end_tick: match r.u64()? {
+                end_tick if end_tick >= start_tick => end_tick,
+                _ => return Err(DecodeError::InvalidValue),
+            },
+        })
+    }
+}
+
+impl Encode for BulletSource {
+    fn encode(&self, w: &mut Writer) {
+        w.u8(match self {
+            BulletSource::Player => 0,
+            BulletSource::Ufo => 1,
+        });
+    }
+
+    fn decode(r: &mut Reader) -> Result<BulletSource, DecodeError> {
+        match r.u8()? {
+            0 => Ok(BulletSource::Player),
+            1 => Ok(BulletSource::Ufo),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+}
+
+impl Encode for Bullet {
+    fn encode(&self, w: &mut Writer) {
+        self.pos.encode(w);
+        self.prev_pos.encode(w);
+        self.speed.encode(w);
+        w.u64(self.lifetime);
+        w.bool(self.dead);
+        self.source.encode(w);
+        w.bool(self.piercing);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Bullet, DecodeError> {
+        Ok(Bullet {
+            pos: Vec2D::decode(r)?,
+            prev_pos: Vec2D::decode(r)?,
+            speed: Vec2D::decode(r)?,
+            lifetime: r.u64()?,
+            dead: r.bool()?,
+            source: BulletSource::decode(r)?,
+            piercing: r.bool()?,
+        })
+    }
+}
+
+impl Encode for Asteroid {
+    fn encode(&self, w: &mut Writer) {
+        self.pos.encode(w);
+        self.prev_pos.encode(w);
+        self.speed.encode(w);
+        w.f64(self.angle);
+        w.f64(self.prev_angle);
+        w.f64(self.angle_speed);
+        w.f64(self.size);
+        self.size_class.encode(w);
+        w.usize(self.style);
+        w.bool(self.dead);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Asteroid, DecodeError> {
+        Ok(Asteroid {
+            pos: Vec2D::decode(r)?,
+            prev_pos: Vec2D::decode(r)?,
+            speed: Vec2D::decode(r)?,
+            angle: r.f64()?,
+            prev_angle: r.f64()?,
+            angle_speed: r.f64()?,
+            size: r.f64()?,
+            size_class: AsteroidSize::decode(r)?,
+            style: match r.usize()? {
+                style if style < ASTEROID_SHAPES.len() => style,
+                _ => return Err(DecodeError::InvalidValue),
+            },
+            dead: r.bool()?,
+        })
+    }
+}
+
+impl Encode for UfoKind {
+    fn encode(&self, w: &mut Writer) {
+        w.u8(match self {
+            UfoKind::Large => 0,
+            UfoKind::Small => 1,
+        });
+    }
+
+    fn decode(r: &mut Reader) -> Result<UfoKind, DecodeError> {
+        match r.u8()? {
+            0 => Ok(UfoKind::Large),
+            1 => Ok(UfoKind::Small),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+}
+
+impl Encode for Ufo {
+    fn encode(&self, w: &mut Writer) {
+        self.kind.encode(w);
+        self.pos.encode(w);
+        self.prev_pos.encode(w);
+        self.speed.encode(w);
+        w.f64(self.distance_left);
+        w.u64(self.next_turn_tick);
+        w.u64(self.next_shot_tick);
+        w.bool(self.dead);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Ufo, DecodeError> {
+        Ok(Ufo {
+            kind: UfoKind::decode(r)?,
+            pos: Vec2D::decode(r)?,
+            prev_pos: Vec2D::decode(r)?,
+            speed: Vec2D::decode(r)?,
+            distance_left: r.f64()?,
+            next_turn_tick: r.u64()?,
+            next_shot_tick: r.u64()?,
+            dead: r.bool()?,
+        })
+    }
+}
+
+impl Encode for PowerUpKind {
+    fn encode(&self, w: &mut Writer) {
+        w.u8(*self as u8);
+    }
+
+    fn decode(r: &mut Reader) -> Result<PowerUpKind, DecodeError> {
+        PowerUpKind::ALL
+            .get(r.u8()? as usize)
+            .copied()
+            .ok_or(DecodeError::InvalidValue)
+    }
+}
+
+impl Encode for PowerUp {
+    fn encode(&self, w: &mut Writer) {
+        self.kind.encode(w);
+        self.pos.encode(w);
+        self.prev_pos.encode(w);
+        self.speed.encode(w);
+        w.u64(self.lifetime);
+        w.bool(self.dead);
+    }
+
+    fn decode(r: &mut Reader) -> Result<PowerUp, DecodeError> {
+        Ok(PowerUp {
+            kind: PowerUpKind::decode(r)?,
+            pos: Vec2D::decode(r)?,
+            prev_pos: Vec2D::decode(r)?,
+            speed: Vec2D::decode(r)?,
+            lifetime: r.u64()?,
+            dead: r.bool()?,
+        })
+    }
+}
+
+impl Encode for PlayerState {
+    fn encode(&self, w: &mut Writer) {
+        w.u64(self.score);
+        w.u64(self.lives);
+        w.u64(self.next_bonus_life);
+        w.usize(self.level);
+        self.asteroids.encode(w);
+    }
+
+    fn decode(r: &mut Reader) -> Result<PlayerState, DecodeError> {
+        Ok(PlayerState {
+            score: r.u64()?,
+            lives: r.u64()?,
+            next_bonus_life: r.u64()?,
+            level: match r.usize()? {
+                level if level <= MAX_LEVEL => level,
+                _ => return Err(DecodeError::InvalidValue),
+            },
+            asteroids: Vec::decode(r)?,
+        })
+    }
+}
+
+impl Encode for Explosion {
+    fn encode(&self, w: &mut Writer) {
+        self.pos.encode(w);
+        w.u64(self.start_tick);
+        w.u64(self.lifetime);
+    }
+
+    fn decode(r: &mut Reader) -> Result<Explosion, DecodeError> {
+        let pos = Vec2D::decode(r)?;
+        let start_tick = r.u64()?;
+        Ok(Explosion {
+            pos,
+            start_tick,
+            lifetime: match r.u64()? {
+                lifetime if lifetime >= start_tick => lifetime,
+                _ => return Err(DecodeError::InvalidValue),
+            },
+        })
+    }
+}
@@ -1,27 +1,82 @@
 use crate::game::{Config, InputIndex, Inputs};
 use crate::math::Vec2D;
+use std::f64::consts::{PI, TAU};
+
+/// A hyperspace jump in progress. The ship is already at its destination,
+/// but it can't be seen or hit until `end_tick`.
+#[derive(Clone, Copy, Debug)]
+pub struct Jump {
+    pub from: Vec2D,
+    pub start_tick: u64,
+    pub end_tick: u64,
+}
 
 #[derive(Debug)]
 pub struct Ship {
     pub pos: Vec2D,
+    /// Position and angle before the last tick, for render interpolation.
+    pub prev_pos: Vec2D,
+    pub prev_angle: f64,
     pub speed: Vec2D,
     pub dead: bool,
     pub angle: f64,
     pub angular_speed: f64,
+    pub jump: Option<Jump>,
+    /// Whether the shield is up this tick.
+    pub shield: bool,
+    /// Shield energy, from 0 (empty) to 1 (fully charged).
+    pub energy: f64,
+}
+
+impl Default for Ship {
+    fn default() -> Ship {
+        Ship::new()
+    }
 }
 
 impl Ship {
     pub fn new() -> Ship {
         Ship {
             pos: Vec2D::zero(),
+            prev_pos: Vec2D::zero(),
+            prev_angle: 0.0,
             speed: Vec2D::zero(),
             angle: 0.0,
             angular_speed: 0.0,
             dead: false,
+            jump: None,
+            shield: false,
+            energy: 1.0,
+        }
+    }
+
+    /// Whether the ship is on the field, where it can shoot and be hit.
+    pub fn is_present(&self) -> bool {
+        !self.dead && self.jump.is_none()
+    }
+
+    /// Makes the current position the starting point of interpolation,
+    /// so the ship doesn't appear to fly over after being moved.
+    pub fn settle(&mut self) {
+        self.prev_pos = self.pos;
+        self.prev_angle = self.angle;
+    }
+
+    /// Picks a turn rate that rotates the ship to face `target` without overshooting.
+    fn steer_towards(&self, target: Vec2D, config: &Config) -> f64 {
+        let delta = (target - self.pos).wrap_delta(&config.field_size);
+        if delta.len_squared() < 1.0 {
+            return 0.0;
         }
+        let diff = delta.y.atan2(delta.x) - self.angle;
+        let diff = (diff + PI).rem_euclid(TAU) - PI;
+        // proportional to how far off the ship is, damped by how fast it already turns
+        (diff * 2.0 - self.angular_speed * 0.5).clamp(-1.0, 1.0)
     }
 
     pub fn tick(&mut self, inputs: &Inputs, config: &Config) {
+        self.settle();
+
         // drag
         let drag = self.speed.dot(self.speed) * config.drag;
         self.speed -= self.speed.scale(drag * config.delta_t);
@@ -50,13 +105,26 @@ impl Ship {
         ) {
             (true, false) => -1.0,
             (false, true) => 1.0,
-            _ => 0.0,
+            (false, false) => match inputs.aim() {
+                Some(aim) if inputs.turn() == 0.0 => self.steer_towards(aim, config),
+                _ => inputs.turn(),
+            },
+            (true, true) => 0.0,
         };
         if rotate_dir != 0.0 {
             let accel = rotate_dir * config.angular_accel * config.delta_t;
             self.angular_speed += accel;
         }
 
+        // holding the shield on an empty charge keeps it down, rather than flickering
+        if inputs.is_down(InputIndex::Shield) && !self.dead {
+            self.shield = self.energy > 0.0;
+            self.energy = (self.energy - config.shield_drain * config.delta_t).max(0.0);
+        } else {
+            self.shield = false;
+            self.energy = (self.energy + config.shield_recharge * config.delta_t).min(1.0);
+        }
+
         // limiters
         let speed = self.speed.len();
         if speed > config.speed_limit {
@@ -0,0 +1,170 @@
+use crate::broadphase::SpatialGrid;
+use crate::game::{Config, Game, GameState, Inputs, BROADPHASE_CELL_SIZE};
+use crate::rng::GameRng;
+use crate::serialize::{DecodeError, Encode, Reader, Writer, MAX_LEVEL};
+use crate::ship::Ship;
+
+const MAGIC: &[u8; 4] = b"ASTS";
+/// Bump this whenever the layout of `Game`, its entities or `Config` changes.
+const VERSION: u8 = 14;
+
+/// Saves the complete state of `game`, so it can be continued later with `load`.
+///
+/// Inputs are not saved: keys held at the time of saving aren't held on load.
+pub fn save(game: &Game) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.bytes(MAGIC);
+    w.u8(VERSION);
+    game.config.encode(&mut w);
+    w.u64(game.seed);
+    game.rng.encode(&mut w);
+    game.game_state.encode(&mut w);
+    game.ship.encode(&mut w);
+    game.ufo.encode(&mut w);
+    w.u64(game.ufo_spawn_tick);
+    w.u64(game.lives);
+    w.u64(game.next_bonus_life);
+    game.bonus_life_tick.encode(&mut w);
+    w.usize(game.level);
+    w.u64(game.score);
+    w.usize(game.player);
+    game.waiting_player.encode(&mut w);
+    w.u64(game.tick);
+    w.u64(game.next_bullet_tick);
+    w.u64(game.next_hyperspace_tick);
+    w.u64(game.invulnerable_until);
+    game.explosions.encode(&mut w);
+    game.bullets.encode(&mut w);
+    game.asteroids.encode(&mut w);
+    game.powerups.encode(&mut w);
+    for end in game.effect_ends {
+        w.u64(end);
+    }
+    w.into_bytes()
+}
+
+/// Restores a game saved with `save`.
+pub fn load(bytes: &[u8]) -> Result<Game, DecodeError> {
+    let mut r = Reader::new(bytes);
+    if r.bytes(MAGIC.len())? != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    let version = r.u8()?;
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    let game = Game {
+        config: Config::decode(&mut r)?,
+        seed: r.u64()?,
+        rng: GameRng::decode(&mut r)?,
+        game_state: GameState::decode(&mut r)?,
+        ship: Ship::decode(&mut r)?,
+        ufo: Option::decode(&mut r)?,
+        ufo_spawn_tick: r.u64()?,
+        lives: r.u64()?,
+        next_bonus_life: r.u64()?,
+        bonus_life_tick: Option::decode(&mut r)?,
+        level: match r.usize()? {
+            level if level <= MAX_LEVEL => level,
+            _ => return Err(DecodeError::InvalidValue),
+        },
+        score: r.u64()?,
+        player: match r.usize()? {
+            player @ 0..=1 => player,
+            _ => return Err(DecodeError::InvalidValue),
+        },
+        waiting_player: Option::decode(&mut r)?,
+        tick: r.u64()?,
+        next_bullet_tick: r.u64()?,
+        next_hyperspace_tick: r.u64()?,
+        invulnerable_until: r.u64()?,
+        explosions: Vec::decode(&mut r)?,
+        bullets: Vec::decode(&mut r)?,
+        asteroids: Vec::decode(&mut r)?,
+        powerups: Vec::decode(&mut r)?,
+        effect_ends: [r.u64()?, r.u64()?, r.u64()?],
+        inputs: Inputs::new(),
+        pause_held: false,
+        events: Vec::new(),
+        broadphase: SpatialGrid::new(BROADPHASE_CELL_SIZE),
+    };
+    if !r.is_empty() {
+        return Err(DecodeError::InvalidValue);
+    }
+    // the HUD flash counts the ticks since the last bonus life
+    if game
+        .bonus_life_tick
+        .is_some_and(|awarded| awarded > game.tick)
+    {
+        return Err(DecodeError::InvalidValue);
+    }
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::play;
+    use crate::game::Explosion;
+    use crate::math::Vec2D;
+    use crate::ship::Jump;
+
+    #[test]
+    fn loaded_games_continue_the_same() {
+        let mut game = Game::with_seed(5);
+        play(&mut game, 1000);
+        let bytes = save(&game);
+        let mut loaded = load(&bytes).unwrap();
+        assert_eq!(save(&loaded), bytes);
+
+        // inputs aren't saved, and the scripted inputs only depend on the tick
+        game.inputs = Inputs::new();
+        play(&mut game, 1000);
+        play(&mut loaded, 1000);
+        assert_eq!(save(&loaded), save(&game));
+    }
+
+    #[test]
+    fn truncated_snapshots_are_rejected() {
+        let bytes = save(&Game::with_seed(5));
+        assert!(matches!(
+            load(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEof)
+        ));
+        assert!(matches!(load(b"ASTR"), Err(DecodeError::BadMagic)));
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        let mut game = Game::with_seed(1);
+        game.config.asteroid_sizes[0].fragments = 100_000_000;
+        assert!(matches!(load(&save(&game)), Err(DecodeError::InvalidValue)));
+    }
+
+    #[test]
+    fn impossible_games_are_rejected() {
+        let rejected = |spoil: fn(&mut Game)| {
+            let mut game = Game::with_seed(1);
+            play(&mut game, 100);
+            assert!(load(&save(&game)).is_ok());
+            spoil(&mut game);
+            matches!(load(&save(&game)), Err(DecodeError::InvalidValue))
+        };
+        assert!(rejected(|game| game.level = usize::MAX));
+        assert!(rejected(|game| game.bonus_life_tick = Some(game.tick + 1)));
+        assert!(rejected(|game| {
+            game.explosions.push(Explosion {
+                pos: Vec2D::zero(),
+                start_tick: 50,
+                lifetime: 49,
+            })
+        }));
+        assert!(rejected(|game| {
+            game.ship.jump = Some(Jump {
+                from: Vec2D::zero(),
+                start_tick: 50,
+                end_tick: 49,
+            })
+        }));
+    }
+}
@@ -5,11 +5,51 @@ pub struct Instant {
     now: f64,
 }
 
+/// A monotonic clock reporting time in milliseconds.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// Clock backed by `window.performance.now()`.
+#[cfg(feature = "web")]
+pub struct PerformanceClock;
+
+#[cfg(feature = "web")]
+impl Clock for PerformanceClock {
+    fn now(&self) -> Instant {
+        Instant::from_millis(web_sys::window().unwrap().performance().unwrap().now())
+    }
+}
+
+/// Clock that only advances when told to, for driving the game without a browser.
+#[derive(Default)]
+pub struct ManualClock {
+    now: std::cell::Cell<f64>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration.as_secs_f64() * 1e3);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        Instant::from_millis(self.now.get())
+    }
+}
+
 impl Instant {
+    pub fn from_millis(now: f64) -> Instant {
+        Instant { now }
+    }
+    #[cfg(feature = "web")]
     pub fn now() -> Instant {
-        Instant {
-            now: web_sys::window().unwrap().performance().unwrap().now(),
-        }
+        PerformanceClock.now()
     }
     pub fn duration_since(&self, earlier: Instant) -> Duration {
         let diff = (self.now - earlier.now) * 0.001;
@@ -17,7 +57,148 @@ impl Instant {
         let nano = (diff.fract() * 1e9) as u32;
         Duration::new(secs, nano)
     }
+    #[cfg(feature = "web")]
     pub fn elapsed(&self) -> Duration {
         Instant::now().duration_since(*self)
     }
 }
+
+/// How many fixed ticks to run for a single animation frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Steps {
+    pub ticks: u32,
+    /// Ticks that were due but skipped because the frame was too far behind.
+    pub dropped: u32,
+}
+
+/// Accumulates real time between frames and hands it out in fixed-size ticks,
+/// so the simulation runs at the same speed regardless of the display refresh rate.
+pub struct FixedTimestep {
+    delta_t: f64,
+    max_ticks: u32,
+    last: Option<Instant>,
+    accumulator: f64,
+    dropped: u64,
+}
+
+impl FixedTimestep {
+    /// `delta_t` is the length of a tick in seconds.
+    /// At most `max_ticks` ticks are run per frame, the rest are dropped,
+    /// so a slow frame can't cause ever slower catch-up frames.
+    pub fn new(delta_t: f64, max_ticks: u32) -> FixedTimestep {
+        FixedTimestep {
+            delta_t,
+            max_ticks,
+            last: None,
+            accumulator: 0.0,
+            dropped: 0,
+        }
+    }
+
+    /// Advances the clock to `now` and returns how many ticks are due.
+    pub fn advance(&mut self, now: Instant) -> Steps {
+        let elapsed = match self.last {
+            Some(last) => now.duration_since(last).as_secs_f64(),
+            None => 0.0,
+        };
+        self.last = Some(now);
+        self.accumulator += elapsed;
+
+        let due = (self.accumulator / self.delta_t).floor();
+        self.accumulator -= due * self.delta_t;
+        let due = due as u32;
+        let steps = Steps {
+            ticks: due.min(self.max_ticks),
+            dropped: due.saturating_sub(self.max_ticks),
+        };
+        self.dropped += steps.dropped as u64;
+        steps
+    }
+
+    /// Forgets the time of the last frame, e.g. after the game was paused,
+    /// so the gap isn't counted as time to catch up on.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.accumulator = 0.0;
+    }
+
+    /// How far the clock is between the last tick and the next one, from 0 to 1.
+    pub fn alpha(&self) -> f64 {
+        (self.accumulator / self.delta_t).clamp(0.0, 1.0)
+    }
+
+    /// Total number of ticks dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_are_handed_out_at_a_fixed_rate() {
+        let clock = ManualClock::new();
+        let mut timestep = FixedTimestep::new(0.25, 4);
+        // the first frame only starts the clock
+        assert_eq!(timestep.advance(clock.now()), Steps::default());
+        clock.advance(Duration::from_millis(625));
+        assert_eq!(
+            timestep.advance(clock.now()),
+            Steps {
+                ticks: 2,
+                dropped: 0
+            }
+        );
+        // the 125ms left over carry into the next frame
+        clock.advance(Duration::from_millis(125));
+        assert_eq!(
+            timestep.advance(clock.now()),
+            Steps {
+                ticks: 1,
+                dropped: 0
+            }
+        );
+    }
+
+    #[test]
+    fn slow_frames_drop_ticks() {
+        let clock = ManualClock::new();
+        let mut timestep = FixedTimestep::new(0.25, 4);
+        timestep.advance(clock.now());
+        clock.advance(Duration::from_millis(1750));
+        assert_eq!(
+            timestep.advance(clock.now()),
+            Steps {
+                ticks: 4,
+                dropped: 3
+            }
+        );
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(
+            timestep.advance(clock.now()),
+            Steps {
+                ticks: 4,
+                dropped: 0
+            }
+        );
+        assert_eq!(timestep.dropped(), 3);
+
+        // after a reset, the time in between isn't caught up on
+        clock.advance(Duration::from_secs(60));
+        timestep.reset();
+        assert_eq!(timestep.advance(clock.now()), Steps::default());
+    }
+
+    #[test]
+    fn alpha_is_the_part_of_a_tick_left_over() {
+        let clock = ManualClock::new();
+        let mut timestep = FixedTimestep::new(0.25, 4);
+        timestep.advance(clock.now());
+        assert_eq!(timestep.alpha(), 0.0);
+        clock.advance(Duration::from_millis(625));
+        timestep.advance(clock.now());
+        assert_eq!(timestep.alpha(), 0.5);
+    }
+}
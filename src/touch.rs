@@ -0,0 +1,116 @@
+use crate::game::{InputIndex, Inputs};
+use crate::math::Vec2D;
+
+/// A round on-screen button that holds down `input` while touched.
+pub struct TouchZone {
+    pub input: InputIndex,
+    pub center: Vec2D,
+    pub radius: f64,
+}
+
+impl TouchZone {
+    fn contains(&self, point: Vec2D) -> bool {
+        (point - self.center).len_squared() <= self.radius * self.radius
+    }
+}
+
+/// Virtual controls for touch screens, which have no keys to press.
+///
+/// Every touch presses the zone it is in, and sliding a finger
+/// from one zone to another moves the press along with it.
+pub struct TouchControls {
+    pub zones: Vec<TouchZone>,
+    /// Active touches, by touch identifier, and the zone each one is holding.
+    touches: Vec<(u32, Option<usize>)>,
+    /// The controls are only shown once the screen has been touched.
+    pub visible: bool,
+}
+
+impl TouchControls {
+    /// Lays out turning on the bottom left and thrust and fire on the bottom right.
+    pub fn new(field_size: &Vec2D) -> TouchControls {
+        const RADIUS: f64 = 70.0;
+        const MARGIN: f64 = 110.0;
+        const STEP: f64 = 160.0;
+        let y = field_size.y - MARGIN;
+        let zone = |input, x| TouchZone {
+            input,
+            center: Vec2D { x, y },
+            radius: RADIUS,
+        };
+        TouchControls {
+            zones: vec![
+                zone(InputIndex::Left, MARGIN),
+                zone(InputIndex::Right, MARGIN + STEP),
+                zone(InputIndex::Forward, field_size.x - MARGIN - STEP),
+                zone(InputIndex::Shoot, field_size.x - MARGIN),
+            ],
+            touches: Vec::new(),
+            visible: false,
+        }
+    }
+
+    fn zone_at(&self, point: Vec2D) -> Option<usize> {
+        self.zones.iter().position(|zone| zone.contains(point))
+    }
+
+    /// Whether a finger other than `except` is on a zone for `input`.
+    fn is_held(&self, input: InputIndex, except: u32) -> bool {
+        self.touches.iter().any(|&(id, zone)| {
+            id != except && zone.is_some_and(|zone| self.zones[zone].input == input)
+        })
+    }
+
+    fn press(&self, zone: Option<usize>, inputs: &mut Inputs) {
+        if let Some(zone) = zone {
+            inputs.press(self.zones[zone].input);
+        }
+    }
+
+    fn release(&self, id: u32, zone: Option<usize>, inputs: &mut Inputs) {
+        if let Some(zone) = zone {
+            let input = self.zones[zone].input;
+            if !self.is_held(input, id) {
+                inputs.release(input);
+            }
+        }
+    }
+
+    /// Forgets all active touches, without releasing the inputs they were holding.
+    pub fn clear(&mut self) {
+        self.touches.clear();
+    }
+
+    /// Whether any touch is holding down `zone`.
+    pub fn is_pressed(&self, zone: usize) -> bool {
+        self.touches.iter().any(|&(_, held)| held == Some(zone))
+    }
+
+    pub fn touch_start(&mut self, id: u32, point: Vec2D, inputs: &mut Inputs) {
+        self.visible = true;
+        let zone = self.zone_at(point);
+        self.press(zone, inputs);
+        self.touches.retain(|&(touch, _)| touch != id);
+        self.touches.push((id, zone));
+    }
+
+    pub fn touch_move(&mut self, id: u32, point: Vec2D, inputs: &mut Inputs) {
+        let zone = self.zone_at(point);
+        let Some(idx) = self.touches.iter().position(|&(touch, _)| touch == id) else {
+            return;
+        };
+        let old_zone = self.touches[idx].1;
+        if zone != old_zone {
+            self.touches[idx].1 = zone;
+            self.release(id, old_zone, inputs);
+            self.press(zone, inputs);
+        }
+    }
+
+    pub fn touch_end(&mut self, id: u32, inputs: &mut Inputs) {
+        if let Some(idx) = self.touches.iter().position(|&(touch, _)| touch == id) {
+            let (_, zone) = self.touches.remove(idx);
+            self.release(id, zone, inputs);
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::rc::{Rc, Weak};
+use wasm_bindgen::prelude::*;
+use web_sys::{console, Storage};
+
+#[wasm_bindgen(module = "/js/demo.js")]
+extern "C" {
+    fn svg_set_path(val: &str);
+    fn svg_set_view_box(width: f64, height: f64);
+    fn config_text() -> Option<String>;
+}
+
+fn putstr(s: &str) {
+    console::log_1(&s.into());
+}
+
+use crate::eventloop::{Event, EventLoop};
+use crate::time::{Duration, FixedTimestep, Instant};
+use crate::touch::TouchControls;
+
+use crate::config::Config;
+use crate::game::{Game, GameEvent, InputIndex};
+use crate::math::Vec2D;
+use crate::render_path::{render_game, render_touch_controls};
+use crate::rng::{new_seed, WebCrypto};
+use crate::snapshot;
+
+/// Limits how far the game catches up after a slow frame.
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+const SAVE_KEY: &str = "svg-asteroids-save";
+/// How often, in frames, the game is saved to local storage.
+const SAVE_INTERVAL: u64 = 60;
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(hex, "{:02x}", byte).expect("could not write string");
+    }
+    hex
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Reads the settings embedded in the page, if there are any and they are valid.
+fn load_config() -> Option<Config> {
+    match Config::from_toml(&config_text()?) {
+        Ok(config) => Some(config),
+        Err(err) => {
+            putstr(&format!("invalid config, using defaults: {}", err));
+            None
+        }
+    }
+}
+
+fn load_game() -> Option<Game> {
+    let saved = local_storage()?.get_item(SAVE_KEY).ok()??;
+    match snapshot::load(&from_hex(&saved)?) {
+        Ok(game) => Some(game),
+        Err(err) => {
+            putstr(&format!("could not load saved game: {}", err));
+            None
+        }
+    }
+}
+
+fn save_game(game: &Game) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(SAVE_KEY, &to_hex(&snapshot::save(game)));
+    }
+}
+
+thread_local! {
+    /// The event loop of the running game, if there is one.
+    static GAME_LOOP: RefCell<Option<EventLoop>> = const { RefCell::new(None) };
+    /// The running game, owned by its event loop callback.
+    static GAME: RefCell<Weak<RefCell<Game>>> = const { RefCell::new(Weak::new()) };
+}
+
+/// Runs `f` on the settings of the running game.
+fn with_running_config<T>(f: impl FnOnce(&mut Config) -> T) -> Result<T, JsValue> {
+    let game = GAME
+        .with(|game| game.borrow().upgrade())
+        .ok_or_else(|| JsValue::from_str("the game isn't running"))?;
+    let mut game = game.borrow_mut();
+    Ok(f(&mut game.config))
+}
+
+/// Binds the key `code`, a `KeyboardEvent.code` such as `"KeyQ"`, to the input named `input`,
+/// such as `"Shoot"`. Fails if the key is already bound to another input.
+#[wasm_bindgen]
+pub fn bind_key(code: &str, input: &str) -> Result<(), JsValue> {
+    let input = InputIndex::from_name(input)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown input `{}`", input)))?;
+    with_running_config(|config| config.bind(code, input))?
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/// Lists the keys bound to the input named `input`.
+#[wasm_bindgen]
+pub fn key_bindings(input: &str) -> Result<Vec<String>, JsValue> {
+    let input = InputIndex::from_name(input)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown input `{}`", input)))?;
+    with_running_config(|config| config.bindings(input).map(str::to_string).collect())
+}
+
+/// Unbinds the key `code`, returning the name of the input it triggered.
+#[wasm_bindgen]
+pub fn unbind_key(code: &str) -> Result<Option<String>, JsValue> {
+    with_running_config(|config| config.unbind(code).map(|input| input.name().to_string()))
+}
+
+/// Restores the default key binds.
+#[wasm_bindgen]
+pub fn reset_key_binds() -> Result<(), JsValue> {
+    with_running_config(Config::reset_key_binds)
+}
+
+fn duration_to_ms(duration: &Duration) -> f64 {
+    (duration.as_secs() as f64) * 1e3 + (duration.subsec_nanos() as f64) / 1e6
+}
+#[no_mangle]
+#[wasm_bindgen(start)]
+pub extern "C" fn my_main() {
+    start_game();
+}
+
+/// Stops the running game, saving it first, e.g. when the page unmounts it.
+#[wasm_bindgen]
+pub fn stop_game() {
+    if let Some(mut event_loop) = GAME_LOOP.with(|el| el.borrow_mut().take()) {
+        event_loop.destroy();
+    }
+}
+
+/// Starts the game, continuing a saved one if there is one. Does nothing if it is already running.
+#[wasm_bindgen]
+pub fn start_game() {
+    if GAME_LOOP.with(|el| el.borrow().is_some()) {
+        return;
+    }
+    let config = load_config();
+    let game = Rc::new(RefCell::new(match load_game() {
+        Some(mut game) => {
+            if let Some(config) = config {
+                game.config = config;
+            }
+            game
+        }
+        None => Game::with_config(
+            new_seed(&mut WebCrypto).expect("could not seed rng"),
+            config.unwrap_or_default(),
+        ),
+    }));
+    GAME.with(|weak| *weak.borrow_mut() = Rc::downgrade(&game));
+    let (delta_t, field_size) = {
+        let game = game.borrow();
+        (game.config.delta_t, game.config.field_size)
+    };
+    let mut timestep = FixedTimestep::new(delta_t, MAX_TICKS_PER_FRAME);
+    let mut frame: u64 = 0;
+    let mut touch_controls = TouchControls::new(&field_size);
+    // the field is drawn in its own units, whatever size the settings give it
+    svg_set_view_box(field_size.x, field_size.y);
+
+    let _start = Instant::now();
+
+    let mut event_loop = EventLoop::new(Box::new(move |event, event_loop| {
+        let mut game = game.borrow_mut();
+        let game = &mut *game;
+        match event {
+            Event::Destroyed => {
+                save_game(game);
+                svg_set_path("");
+                putstr("event loop destroyed");
+            }
+            Event::KeyDown {
+                code,
+                chr: _,
+                flags: _,
+            } => {
+                game.inputs.key_down(&code, &game.config);
+            }
+            Event::KeyUp {
+                code,
+                chr: _,
+                flags: _,
+            } => {
+                game.inputs.key_up(&code, &game.config);
+            }
+            Event::GamepadConnected { gamepad } => {
+                putstr(&format!("gamepad {} connected", gamepad));
+            }
+            Event::GamepadDisconnected { gamepad } => {
+                putstr(&format!("gamepad {} disconnected", gamepad));
+                game.inputs.pad_disconnected(&game.config);
+            }
+            Event::GamepadButton {
+                gamepad: _,
+                button,
+                pressed,
+            } => {
+                game.inputs.pad_button(button, pressed, &game.config);
+            }
+            Event::GamepadAxis {
+                gamepad: _,
+                axis,
+                value,
+            } => {
+                game.inputs.pad_axis(axis, value, &game.config);
+            }
+            Event::MouseMove { x, y } => {
+                game.inputs.mouse_move(Vec2D { x, y }, &game.config);
+            }
+            Event::MouseDown { x: _, y: _, button } => {
+                game.inputs.mouse_button(button, true, &game.config);
+            }
+            Event::MouseUp { x: _, y: _, button } => {
+                game.inputs.mouse_button(button, false, &game.config);
+            }
+            Event::Wheel { dx: _, dy: _ } => {}
+            Event::TouchStart { touch, x, y } => {
+                touch_controls.touch_start(touch, Vec2D { x, y }, &mut game.inputs);
+            }
+            Event::TouchMove { touch, x, y } => {
+                touch_controls.touch_move(touch, Vec2D { x, y }, &mut game.inputs);
+            }
+            Event::TouchEnd { touch } => {
+                touch_controls.touch_end(touch, &mut game.inputs);
+            }
+            Event::Blur | Event::VisibilityChange { visible: false } => {
+                // key up events for keys held now won't arrive
+                game.inputs.release_all();
+                // through the inputs, so that a recording of them pauses here too
+                game.request_pause();
+                touch_controls.clear();
+                timestep.reset();
+                // a hidden tab may be closed or discarded without any further events
+                save_game(game);
+            }
+            Event::Focus | Event::VisibilityChange { visible: true } => {}
+            Event::AnimationFrame => {
+                let frame_start = Instant::now();
+                let steps = timestep.advance(frame_start);
+                for _ in 0..steps.ticks {
+                    game.tick();
+                    for event in game.events.iter() {
+                        match event {
+                            GameEvent::BonusLife => {
+                                putstr(&format!("bonus life! {} left", game.lives))
+                            }
+                            GameEvent::PowerUp(kind) => putstr(&format!("power-up: {:?}", kind)),
+                        }
+                    }
+                }
+                let tick_time = frame_start.elapsed();
+                frame += 1;
+
+                let render_start = Instant::now();
+                let mut buf = String::new();
+                render_game(&mut buf, game, timestep.alpha());
+                render_touch_controls(&mut buf, &touch_controls, &game.config.field_size);
+                svg_set_path(&buf);
+                let render_time = render_start.elapsed();
+                let frame_time = frame_start.elapsed();
+
+                if frame.is_multiple_of(512) {
+                    putstr(&format!(
+                        "ticks: {}\ndropped ticks: {} so far\n\
+                         tick time: {:.3}ms\nrender time: {:.3}ms\ntotal time: {:.3}",
+                        steps.ticks,
+                        timestep.dropped(),
+                        duration_to_ms(&tick_time),
+                        duration_to_ms(&render_time),
+                        duration_to_ms(&frame_time)
+                    ));
+                }
+
+                if frame.is_multiple_of(SAVE_INTERVAL) {
+                    save_game(game);
+                }
+
+                event_loop.request_animation_frame();
+            }
+        }
+    }));
+    putstr("event loop started");
+    event_loop.request_animation_frame();
+    GAME_LOOP.with(|el| *el.borrow_mut() = Some(event_loop));
+}